@@ -223,6 +223,11 @@ def main():
         nargs="+",
         help="Specific post ID(s) to download (space-separated)",
     )
+    parser.add_argument(
+        "--posts-file",
+        type=Path,
+        help="JSON list of post ID(s) to download (ie. from `community-archiver ids-check --retry-file`)",
+    )
     parser.add_argument(
         "--browser",
         default="chromium",
@@ -245,7 +250,11 @@ def main():
         archive_path = Path(__file__).parent.parent.parent / "archive"
 
         # Get missing post IDs
-        missing_ids = get_missing_post_ids(limit=args.limit, specific_ids=args.posts)
+        specific_ids = args.posts
+        if args.posts_file is not None:
+            with open(args.posts_file) as fs:
+                specific_ids = (specific_ids or []) + json.load(fs)
+        missing_ids = get_missing_post_ids(limit=args.limit, specific_ids=specific_ids)
 
         if len(missing_ids) == 0:
             print("\n✓ All posts are already downloaded!")
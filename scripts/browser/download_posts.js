@@ -163,7 +163,9 @@ async function extract_post() {
 
   // extract the html
   console.log(`Extracted post ${id}`);
-  const html = content.outerHTML;
+  // keep UI language so numbers and times can be parsed with the right locale
+  const lang = document.documentElement.lang;
+  const html = `<html lang="${lang}"><body>${content.outerHTML}</body></html>`;
 
   return { id, html };
 }
@@ -1,7 +1,7 @@
 from json import load, dump
 
 with open("data/posts.json") as fs:
-    posts = load(fs)
+    posts = load(fs)["posts"]
 
 ids = list()
 for post in posts:
@@ -9,7 +9,10 @@ for post in posts:
     comments = post['comments']
     if comments is None:
         continue
-    n = sum('![]()' in comment['content'] for comment in comments)
+    n = sum(
+        any(span['type'] == 'emote' and not span['url'] for span in comment['content'])
+        for comment in comments
+    )
     if n > 0:
         print("Found", n, "blank images in", id)
         ids.append(id)
@@ -0,0 +1,154 @@
+use color_eyre::{Result, eyre::Context};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{model::PostWithMeta, parse::PARSER_VERSION};
+
+/// Cache of parsed posts, keyed by the hash of the source html and everything that affects parsing.
+#[derive(Debug)]
+pub struct ParseCache {
+    dir: PathBuf,
+    /// Hash of run-wide parse options (ie. parser version, locale, emote mapping).
+    fingerprint: blake3::Hash,
+    used: Mutex<HashSet<String>>,
+}
+
+impl ParseCache {
+    pub fn open(dir: &Path, options: &[&[u8]]) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("fail to create cache dir {}", dir.display()))?;
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&PARSER_VERSION.to_le_bytes());
+        for option in options {
+            hasher.update(&(option.len() as u64).to_le_bytes());
+            hasher.update(option);
+        }
+
+        Ok(Self {
+            dir: dir.to_owned(),
+            fingerprint: hasher.finalize(),
+            used: Mutex::default(),
+        })
+    }
+
+    /// Cache key of a post, `extra` is for per-post inputs (ie. post id and capture time).
+    pub fn key(&self, content: &str, extra: &[&[u8]]) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.fingerprint.as_bytes());
+        for extra in extra {
+            hasher.update(&(extra.len() as u64).to_le_bytes());
+            hasher.update(extra);
+        }
+        hasher.update(content.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension("json")
+    }
+
+    /// Get cached post, unreadable entries are treated as missing.
+    pub fn get(&self, key: &str) -> Option<PostWithMeta> {
+        let file = File::open(self.path(key)).ok()?;
+        let post = serde_json::from_reader(BufReader::new(file)).ok()?;
+        self.used.lock().unwrap().insert(key.to_owned());
+        Some(post)
+    }
+
+    pub fn insert(&self, key: &str, post: &PostWithMeta) -> Result<()> {
+        let path = self.path(key);
+        // written aside then renamed, so readers never see a partial entry
+        let part = path.with_extension("part");
+        let mut writer = BufWriter::new(File::create(&part)?);
+        serde_json::to_writer(&mut writer, post)
+            .wrap_err_with(|| format!("fail to write cache entry {}", path.display()))?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&part, &path)
+            .wrap_err_with(|| format!("fail to write cache entry {}", path.display()))?;
+        self.used.lock().unwrap().insert(key.to_owned());
+        Ok(())
+    }
+
+    /// Remove entries that were not used in this run, returns number of removed entries.
+    pub fn prune(&self) -> Result<usize> {
+        let used = self.used.lock().unwrap();
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if !used.contains(key) {
+                match fs::remove_file(&path) {
+                    Ok(()) => removed += 1,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tests::post;
+
+    #[test]
+    fn test_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ParseCache::open(dir.path(), &[b"th"]).unwrap();
+        let other = ParseCache::open(dir.path(), &[b"en"]).unwrap();
+
+        let key = cache.key("<html></html>", &[b"a", b""]);
+        assert_eq!(key, cache.key("<html></html>", &[b"a", b""]));
+        assert_ne!(key, cache.key("<html> </html>", &[b"a", b""]));
+        assert_ne!(
+            key,
+            cache.key("<html></html>", &[b"a", b"2024-01-17T12:00:00Z"])
+        );
+        // same content saved under another post id
+        assert_ne!(key, cache.key("<html></html>", &[b"a (1)", b""]));
+        assert_ne!(key, cache.key("<html></html>", &[b"", b"a"]));
+        assert_ne!(key, other.key("<html></html>", &[b"a", b""]));
+    }
+
+    #[test]
+    fn test_get_insert_prune() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let cache = ParseCache::open(dir.path(), &[]).unwrap();
+        cache.insert(&cache.key("a", &[]), &post("a")).unwrap();
+        cache.insert(&cache.key("b", &[]), &post("b")).unwrap();
+        assert!(
+            fs::read_dir(dir.path()).unwrap().all(|entry| entry
+                .unwrap()
+                .path()
+                .extension()
+                .unwrap()
+                == "json")
+        );
+
+        // next run only sees `a`
+        let cache = ParseCache::open(dir.path(), &[]).unwrap();
+        assert!(cache.get(&cache.key("c", &[])).is_none());
+        let cached = cache.get(&cache.key("a", &[])).unwrap();
+        assert_eq!(cached.post.id, "a");
+        assert_eq!(cache.prune().unwrap(), 1);
+
+        let cache = ParseCache::open(dir.path(), &[]).unwrap();
+        assert!(cache.get(&cache.key("a", &[])).is_some());
+        assert!(cache.get(&cache.key("b", &[])).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+pub mod check;
+pub mod emotes;
+pub mod export;
+pub mod ids_check;
+pub mod images;
+pub mod migrate;
+pub mod process;
+pub mod schema;
+pub mod stats;
+
+use color_eyre::{
+    Result, Section,
+    eyre::{Context, eyre},
+};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    emote::EmoteResolver,
+    jsonl,
+    migrate::schema_version,
+    model::{Archive, PostWithMeta, SCHEMA_VERSION},
+};
+
+/// Options shared by every command.
+#[derive(clap::Args)]
+pub struct Common {
+    #[arg(long, value_name = "DIR", default_value = "archive", global = true)]
+    /// Directory containing downloaded posts html.
+    pub archive_dir: PathBuf,
+    #[arg(long, value_name = "DIR", default_value = "data", global = true)]
+    /// Directory containing input data (post_ids.json, emote_mapping.json and emote_mapping_default.json).
+    pub data_dir: PathBuf,
+    #[arg(long, value_name = "DIR", default_value = "data", global = true)]
+    /// Directory to write outputs (posts.json, reports) into.
+    pub output_dir: PathBuf,
+    #[arg(long = "emote-mapping", value_name = "FILE", global = true)]
+    /// Additional emote mapping file (`{"<id>": "<name>"}`), loaded after the ones in data dir. Can be repeated.
+    pub emote_mappings: Vec<PathBuf>,
+    #[arg(long, value_name = "FILE", global = true)]
+    /// Processed posts to read, `.json` or `.jsonl`. Defaults to `posts.json` or `posts.jsonl` in output dir, whichever exists.
+    pub posts: Option<PathBuf>,
+}
+
+impl Common {
+    /// Post ids in the order they appear on the channel, use for ordering outputs.
+    pub fn post_ids_file(&self) -> PathBuf {
+        self.data_dir.join("post_ids.json")
+    }
+
+    pub fn posts_file(&self) -> PathBuf {
+        self.output_dir.join("posts.json")
+    }
+
+    /// Posts written by `process --format jsonl`.
+    pub fn posts_jsonl_file(&self) -> PathBuf {
+        self.output_dir.join("posts.jsonl")
+    }
+
+    /// Read post ids, returning empty ids if the file is missing or invalid.
+    pub fn read_post_ids_lenient(&self) -> Vec<String> {
+        let post_ids_file = self.post_ids_file();
+        let Ok(post_ids) = fs::read_to_string(&post_ids_file) else {
+            eprintln!("Failed to read post ids file: {}", post_ids_file.display());
+            return Vec::new();
+        };
+        let Ok(post_ids) = serde_json::from_str::<Vec<String>>(post_ids.as_str()) else {
+            eprintln!("Failed to parse post ids file: {}", post_ids_file.display());
+            return Vec::new();
+        };
+        post_ids
+    }
+
+    pub fn read_post_ids(&self) -> Result<Vec<String>> {
+        let post_ids_file = self.post_ids_file();
+        let post_ids = fs::read_to_string(&post_ids_file)
+            .wrap_err_with(|| format!("fail to read {}", post_ids_file.display()))?;
+        serde_json::from_str(&post_ids)
+            .wrap_err_with(|| format!("fail to parse {}", post_ids_file.display()))
+    }
+
+    pub fn emote_resolver(&self) -> Result<EmoteResolver> {
+        EmoteResolver::load(&self.data_dir, &self.emote_mappings)
+    }
+
+    pub fn read_posts(&self) -> Result<Vec<PostWithMeta>> {
+        Ok(self.read_archive()?.posts)
+    }
+
+    /// Processed posts to read, `--posts` or the one of posts.json and posts.jsonl that exists.
+    pub fn input_file(&self) -> Result<PathBuf> {
+        if let Some(posts) = &self.posts {
+            return Ok(posts.clone());
+        }
+        let posts_file = self.posts_file();
+        let jsonl_file = self.posts_jsonl_file();
+        match (posts_file.exists(), jsonl_file.exists()) {
+            (true, true) => Err(eyre!(
+                "both {} and {} exist",
+                posts_file.display(),
+                jsonl_file.display()
+            ))
+            .suggestion("choose one with `--posts <FILE>`, or remove the stale one"),
+            (false, true) => Ok(jsonl_file),
+            _ => Ok(posts_file),
+        }
+    }
+
+    /// Read the processed posts of [`Common::input_file`], which must be of the current schema version.
+    pub fn read_archive(&self) -> Result<Archive> {
+        let path = self.input_file()?;
+        let open = || -> Result<_> {
+            Ok(BufReader::new(File::open(&path).wrap_err_with(|| {
+                format!("fail to open {}", path.display())
+            })?))
+        };
+        let check_version = |value: &serde_json::Value| -> Result<()> {
+            let version = schema_version(value)
+                .wrap_err_with(|| format!("fail to parse {}", path.display()))?;
+            if version != SCHEMA_VERSION {
+                return Err(eyre!(
+                    "{} is of schema version {version}, expected {SCHEMA_VERSION}",
+                    path.display()
+                ))
+                .suggestion("run `community-archiver migrate` to upgrade it");
+            }
+            Ok(())
+        };
+
+        if is_jsonl(&path) {
+            let header = jsonl::read_header(open()?)
+                .wrap_err_with(|| format!("fail to parse {}", path.display()))?;
+            check_version(&header)?;
+            return jsonl::Reader::new(open()?)
+                .and_then(jsonl::Reader::into_archive)
+                .wrap_err_with(|| format!("fail to parse {}", path.display()));
+        }
+
+        let value: serde_json::Value = serde_json::from_reader(open()?)
+            .wrap_err_with(|| format!("fail to parse {}", path.display()))?;
+        check_version(&value)?;
+        serde_json::from_value(value).wrap_err_with(|| format!("fail to parse {}", path.display()))
+    }
+}
+
+/// Whether `path` is JSON Lines (posts.jsonl) rather than a single JSON document.
+pub fn is_jsonl(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tests::post;
+
+    #[test]
+    fn test_read_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut common = Common {
+            archive_dir: dir.path().join("archive"),
+            data_dir: dir.path().to_owned(),
+            output_dir: dir.path().to_owned(),
+            emote_mappings: Vec::new(),
+            posts: None,
+        };
+        let archive = Archive::new(vec![post("a")], None);
+        fs::write(
+            common.posts_file(),
+            serde_json::to_string(&archive).unwrap(),
+        )
+        .unwrap();
+        let mut jsonl = Vec::new();
+        jsonl::write(&mut jsonl, &Archive::new(vec![post("b")], None)).unwrap();
+        fs::write(common.posts_jsonl_file(), &jsonl).unwrap();
+
+        let err = common.read_archive().unwrap_err();
+        assert!(err.to_string().starts_with("both "), "{err}");
+        common.posts = Some(common.posts_jsonl_file());
+        assert_eq!(common.read_archive().unwrap().posts[0].post.id, "b");
+
+        common.posts = None;
+        fs::remove_file(common.posts_file()).unwrap();
+        assert_eq!(common.read_archive().unwrap().posts[0].post.id, "b");
+
+        // older jsonl is left for `migrate`
+        let old = String::from_utf8(jsonl).unwrap().replacen(
+            &format!(r#""schema_version":{SCHEMA_VERSION}"#),
+            r#""schema_version":1"#,
+            1,
+        );
+        fs::write(common.posts_jsonl_file(), old).unwrap();
+        let err = common.read_archive().unwrap_err();
+        assert!(
+            err.to_string().ends_with(&format!(
+                "is of schema version 1, expected {SCHEMA_VERSION}"
+            )),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use color_eyre::eyre::{Result, ensure, eyre};
+use indicatif::ParallelProgressIterator;
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    fs::{File, read_dir},
+    path::PathBuf,
+};
+
+use crate::{cmd::Common, locale::Locale, rules::Ruleset};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[arg(long, value_enum)]
+    /// UI language of the built-in ruleset.
+    /// Detected from each page's `<html lang>` if not specified, falling back to Thai.
+    locale: Option<Locale>,
+    #[arg(long, value_name = "FILE")]
+    /// Ruleset (`.json` or `.toml`) to check with instead of the built-in one, see `rules/` for examples.
+    ruleset: Option<PathBuf>,
+}
+
+pub fn run(common: &Common, args: Args) -> Result<()> {
+    let ruleset = args.ruleset.as_deref().map(Ruleset::load).transpose()?;
+    let builtin = Locale::ALL
+        .into_iter()
+        .map(|locale| (locale, Ruleset::builtin(locale)))
+        .collect::<HashMap<_, _>>();
+    let ruleset_for = |html: &scraper::Html| {
+        ruleset.as_ref().unwrap_or_else(|| {
+            let locale = args
+                .locale
+                .or_else(|| Locale::detect(html))
+                .unwrap_or_default();
+            &builtin[&locale]
+        })
+    };
+
+    let dirs = read_dir(&common.archive_dir)?.collect::<std::io::Result<Vec<_>>>()?;
+
+    let dirs_len = dirs.len();
+    let invalid = dirs
+        .into_par_iter()
+        .progress()
+        .filter(|fs| fs.file_type().is_ok_and(|ft| ft.is_file()))
+        .map(|fs| {
+            let path = fs.path();
+            let content = std::fs::read_to_string(&path)?;
+            let html = scraper::Html::parse_document(&content);
+            let errs = ruleset_for(&html).check(&content, &html);
+            if errs.is_empty() {
+                return Ok(None);
+            }
+            println!("{} is not valid: {:?}", fs.file_name().display(), errs);
+
+            let id = path
+                .file_stem()
+                .unwrap()
+                .to_owned()
+                .into_string()
+                .map_err(|ostr| eyre!("{:?} should be utf-8", ostr))?;
+            Ok::<_, color_eyre::eyre::Error>(Some((id, errs)))
+        })
+        .filter_map(|r| r.transpose())
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    println!(
+        "{}/{} invalid ({:.2}%)",
+        invalid.len(),
+        dirs_len,
+        100.0 * (invalid.len() as f64 / dirs_len as f64)
+    );
+    let ids = common.read_post_ids()?;
+    serde_json::to_writer_pretty(
+        File::create(common.output_dir.join("invalid.json"))?,
+        &invalid,
+    )?;
+    serde_json::to_writer_pretty(
+        File::create(common.output_dir.join("invalid_ids.json"))?,
+        &invalid
+            .keys()
+            .sorted_by_key(|iv| ids.iter().position(|x| x == *iv))
+            .collect::<Vec<_>>(),
+    )?;
+
+    ensure!(invalid.is_empty());
+    Ok(())
+}
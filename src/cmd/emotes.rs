@@ -0,0 +1,144 @@
+use color_eyre::{Result, eyre::ensure};
+use itertools::Itertools;
+use std::{collections::BTreeMap, path::PathBuf};
+use url::Url;
+
+use crate::{
+    cmd::Common,
+    emote::{EMOTE_BASE_URL, EmoteResolver, emote_id},
+    http,
+    model::{CommentThread, PostWithMeta},
+    rich_text::{RichText, Span},
+};
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// List mapped custom emotes, as `<id>\t<name>\t<mapping file>` sorted by name.
+    List,
+    /// Download every mapped custom emote image, and report emotes in posts.json missing from the mapping.
+    Sync {
+        #[arg(long, value_name = "DIR", default_value = "emote")]
+        /// Directory to download emote images into, as `<name>.png`.
+        emote_dir: PathBuf,
+        #[arg(long, default_value_t = 240)]
+        /// Image size in pixels.
+        size: u32,
+        #[arg(long, value_name = "URL", default_value = EMOTE_BASE_URL)]
+        /// Base url of emote images.
+        base_url: Url,
+    },
+}
+
+pub fn run(common: &Common, command: Command) -> Result<()> {
+    let resolver = common.emote_resolver()?;
+    for conflict in resolver.conflicts() {
+        eprintln!("Warning: {conflict}");
+    }
+    match command {
+        Command::List => {
+            for (id, name) in resolver
+                .mapping()
+                .iter()
+                .sorted_by_key(|(id, name)| (*name, *id))
+            {
+                let source = resolver.source(id).map(|s| s.display().to_string());
+                println!("{id}\t{name}\t{}", source.unwrap_or_default());
+            }
+            eprintln!("{} emotes", resolver.mapping().len());
+        }
+        Command::Sync {
+            emote_dir,
+            size,
+            base_url,
+        } => {
+            let report = resolver.sync(&http::agent(), &base_url, &emote_dir, size)?;
+            for (name, err) in &report.failed {
+                eprintln!("Failed to download {name}: {err}");
+            }
+            println!(
+                "{} emotes downloaded, {} up to date, {} failed",
+                report.downloaded.len(),
+                report.skipped.len(),
+                report.failed.len()
+            );
+
+            match common.read_posts() {
+                Ok(posts) => {
+                    let unmapped = unmapped_emotes(&posts, &resolver);
+                    for (id, (names, count)) in &unmapped {
+                        println!(
+                            "Unmapped emote {id} ({}) used {count} times",
+                            names.iter().join(", ")
+                        );
+                    }
+                    if !unmapped.is_empty() {
+                        println!(
+                            "{} emotes are missing from the mapping, their images are not downloaded",
+                            unmapped.len()
+                        );
+                    }
+                }
+                Err(err) => eprintln!("Skipping unmapped emotes report: {err}"),
+            }
+
+            ensure!(
+                report.failed.is_empty(),
+                "{} emotes failed to download",
+                report.failed.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ids of custom emotes used in posts but missing from the mapping,
+/// with their names from alt text (if any) and number of uses.
+fn unmapped_emotes(
+    posts: &[PostWithMeta],
+    resolver: &EmoteResolver,
+) -> BTreeMap<String, (Vec<String>, usize)> {
+    fn visit(
+        text: &RichText,
+        resolver: &EmoteResolver,
+        unmapped: &mut BTreeMap<String, (Vec<String>, usize)>,
+    ) {
+        for span in &text.0 {
+            let Span::Emote { name, url } = span else {
+                continue;
+            };
+            let Some(id) = emote_id(url) else {
+                continue;
+            };
+            if resolver.mapping().contains_key(id) {
+                continue;
+            }
+            let (names, count) = unmapped.entry(id.to_owned()).or_default();
+            *count += 1;
+            if let Some(name) = name
+                && !names.contains(name)
+            {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    fn visit_threads(
+        threads: &[CommentThread],
+        resolver: &EmoteResolver,
+        unmapped: &mut BTreeMap<String, (Vec<String>, usize)>,
+    ) {
+        for thread in threads {
+            visit(&thread.comment.content, resolver, unmapped);
+            visit_threads(&thread.replies, resolver, unmapped);
+        }
+    }
+
+    let mut unmapped = BTreeMap::new();
+    for post in posts {
+        visit(&post.post.main.content, resolver, &mut unmapped);
+        if let Some(comments) = &post.post.comments {
+            visit_threads(comments, resolver, &mut unmapped);
+        }
+    }
+    unmapped
+}
@@ -0,0 +1,248 @@
+mod authors;
+mod markdown;
+mod site;
+mod sqlite;
+
+use color_eyre::{
+    Result,
+    eyre::{Context, ensure},
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cmd::{
+        Common,
+        images::{Manifest, percent_encode, read_manifest},
+    },
+    model::{CommentThread, PostWithMeta},
+    rich_text::{Format, encode_url},
+};
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Export posts.json, optionally filtered and pretty-printed.
+    Json {
+        #[arg(long = "id", value_name = "ID")]
+        /// Only export posts with these ids.
+        ids: Vec<String>,
+        #[arg(long)]
+        pretty: bool,
+        #[arg(long, value_name = "FORMAT")]
+        /// Render post and comment content into a string instead of spans.
+        content: Option<Format>,
+        #[arg(long, value_name = "FILE")]
+        /// Write to file instead of stdout.
+        output: Option<PathBuf>,
+    },
+    /// Export posts into a SQLite database, with full-text search over post and comment content.
+    Sqlite {
+        #[arg(long, value_name = "FILE")]
+        /// Database file to write, replaced if it exists. Defaults to `posts.sqlite` in output dir.
+        output: Option<PathBuf>,
+    },
+    /// Export every post into a Markdown file with YAML front matter, ie. for note-taking apps like Obsidian.
+    Markdown {
+        #[arg(long, value_name = "DIR")]
+        /// Directory to write `<id>.md` files into. Defaults to `markdown` in output dir.
+        output: Option<PathBuf>,
+        #[arg(long, value_name = "DIR", default_value = "archive_imgs")]
+        /// Directory of the `images` command, images not downloaded are linked to their origin.
+        images_dir: PathBuf,
+    },
+    /// Export an index of comment authors, deduped across posts by channel id or handle, with their comment counts.
+    Authors {
+        #[arg(long, value_name = "FILE")]
+        /// File to write. Defaults to `authors.json` in output dir.
+        output: Option<PathBuf>,
+    },
+    /// Export posts into a static HTML site for browsing offline, using the downloaded images and emotes.
+    Site {
+        #[arg(long, value_name = "DIR")]
+        /// Directory to write the site into. Defaults to `site` in output dir.
+        output: Option<PathBuf>,
+        #[arg(long, value_name = "DIR", default_value = "archive_imgs")]
+        /// Directory of the `images` command, images not downloaded are linked to their origin.
+        images_dir: PathBuf,
+        #[arg(long, value_name = "DIR", default_value = "emote")]
+        /// Directory of `emotes sync`.
+        emote_dir: PathBuf,
+        #[arg(long, default_value_t = 20)]
+        /// Number of posts per index page.
+        per_page: usize,
+    },
+}
+
+pub fn run(common: &Common, command: Command) -> Result<()> {
+    match command {
+        Command::Json {
+            ids,
+            pretty,
+            content,
+            output,
+        } => {
+            let mut posts = common.read_posts()?;
+            if !ids.is_empty() {
+                posts.retain(|post| ids.contains(&post.post.id));
+                ensure!(!posts.is_empty(), "no posts found with ids {ids:?}");
+            }
+
+            let posts = posts
+                .iter()
+                .map(|post| {
+                    let mut value = serde_json::to_value(post)?;
+                    if let Some(format) = content {
+                        render_content(post, &mut value, format);
+                    }
+                    Ok(value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut writer: Box<dyn Write> = match output {
+                Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+                None => Box::new(BufWriter::new(io::stdout().lock())),
+            };
+            if pretty {
+                serde_json::to_writer_pretty(&mut writer, &posts)?;
+            } else {
+                serde_json::to_writer(&mut writer, &posts)?;
+            }
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+        Command::Sqlite { output } => {
+            let posts = common.read_posts()?;
+            let output = output.unwrap_or_else(|| common.output_dir.join("posts.sqlite"));
+            sqlite::write(&posts, &output)?;
+            println!("Exported {} posts into {}", posts.len(), output.display());
+        }
+        Command::Markdown { output, images_dir } => {
+            let posts = common.read_posts()?;
+            let output = output.unwrap_or_else(|| common.output_dir.join("markdown"));
+            let manifest = read_manifest(&images_dir.join("manifest.json"))?;
+            let images = markdown::write(&posts, &manifest, &images_dir, &output)?;
+            println!(
+                "Exported {} posts into {}, with {} images",
+                posts.len(),
+                output.display(),
+                images
+            );
+        }
+        Command::Authors { output } => {
+            let posts = common.read_posts()?;
+            let output = output.unwrap_or_else(|| common.output_dir.join("authors.json"));
+            let authors = authors::index(&posts);
+            let mut writer = BufWriter::new(
+                File::create(&output)
+                    .wrap_err_with(|| format!("fail to create {}", output.display()))?,
+            );
+            serde_json::to_writer_pretty(&mut writer, &authors)?;
+            writeln!(writer)?;
+            writer.flush()?;
+            println!(
+                "Exported {} authors of {} comments into {}",
+                authors.len(),
+                authors.iter().map(|a| a.comments).sum::<usize>(),
+                output.display()
+            );
+        }
+        Command::Site {
+            output,
+            images_dir,
+            emote_dir,
+            per_page,
+        } => {
+            let archive = common.read_archive()?;
+            let output = output.unwrap_or_else(|| common.output_dir.join("site"));
+            let assets = site::Assets {
+                images: read_manifest(&images_dir.join("manifest.json"))?,
+                images_dir,
+                emote_dir,
+            };
+            let summary = site::write(&archive, &assets, per_page, &output)?;
+            println!(
+                "Exported {} posts in {} pages into {}, with {} images and {} emotes",
+                archive.posts.len(),
+                summary.pages,
+                output.display(),
+                summary.images,
+                summary.emotes
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Replace content spans in the serialized `post` with rendered strings.
+fn render_content(post: &PostWithMeta, value: &mut Value, format: Format) {
+    fn render_threads(threads: &[CommentThread], values: &mut Value, format: Format) {
+        let Some(values) = values.as_array_mut() else {
+            return;
+        };
+        for (thread, value) in threads.iter().zip(values) {
+            value["content"] = thread.comment.content.render(format).into();
+            if let Some(replies) = value.get_mut("replies") {
+                render_threads(&thread.replies, replies, format);
+            }
+        }
+    }
+
+    value["content"] = post.post.main.content.render(format).into();
+    if let (Some(comments), Some(values)) = (&post.post.comments, value.get_mut("comments")) {
+        render_threads(comments, values, format);
+    }
+}
+
+/// Downloaded images copied next to an export, by url as in the images manifest.
+struct LocalImages<'a>(HashMap<&'a str, &'a str>);
+
+impl<'a> LocalImages<'a> {
+    /// Copy the images of `manifest` found in `images_dir` into `dir`, images missing from `images_dir` are left out.
+    fn copy(manifest: &'a Manifest, images_dir: &Path, dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).wrap_err_with(|| format!("fail to create {}", dir.display()))?;
+        let mut images = HashMap::new();
+        for (url, entry) in manifest {
+            let src = images_dir.join(&entry.file);
+            if src.exists() {
+                copy_if_changed(&src, &dir.join(&entry.file))?;
+                images.insert(url.as_str(), entry.file.as_str());
+            }
+        }
+        Ok(Self(images))
+    }
+
+    /// File name of the image at `url`, if downloaded.
+    fn get(&self, url: &str) -> Option<&'a str> {
+        // the manifest is keyed by url without query
+        self.0.get(url.split('?').next().unwrap_or(url)).copied()
+    }
+
+    /// Src of the image at `url` for a page `root` away from the export root (ie. `../`),
+    /// the local copy if downloaded. Percent-encoded, so it can be used as a Markdown link destination as is.
+    fn src(&self, url: &str, root: &str) -> String {
+        match self.get(url) {
+            // file names are percent-encoded urls, which must be encoded again to refer to the file
+            Some(file) => format!("{root}images/{}", percent_encode(file)),
+            None => encode_url(url),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Copy unless `dst` already has the same size, so re-exporting does not copy every image again.
+fn copy_if_changed(src: &Path, dst: &Path) -> Result<()> {
+    let len = |path: &Path| fs::metadata(path).map(|m| m.len()).ok();
+    if len(dst).is_none() || len(src) != len(dst) {
+        fs::copy(src, dst)
+            .wrap_err_with(|| format!("fail to copy {} to {}", src.display(), dst.display()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{Comment, CommentThread, PostWithMeta};
+
+/// Commenter across all posts.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct Author {
+    pub channel_id: Option<String>,
+    pub handle: Option<String>,
+    pub url: Option<String>,
+    /// Names the author is displayed as, in order of appearance.
+    pub names: Vec<String>,
+    /// Avatar of their first comment, in posts.json order.
+    pub avatar: Option<String>,
+    /// Comments including replies.
+    pub comments: usize,
+    /// Posts they commented on.
+    pub posts: usize,
+}
+
+/// What an author is recognized by, the display name only when the page links to no channel.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Key {
+    ChannelId(String),
+    Handle(String),
+    Name(String),
+}
+
+impl Key {
+    fn of(comment: &Comment) -> Vec<Self> {
+        let channel = &comment.author_channel;
+        let keys = [
+            channel.channel_id.clone().map(Self::ChannelId),
+            channel.handle.clone().map(Self::Handle),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if keys.is_empty() {
+            vec![Self::Name(comment.author.clone())]
+        } else {
+            keys
+        }
+    }
+}
+
+impl Author {
+    /// Take the ids this author is missing and the names and counts of `other`, the same author.
+    fn merge(&mut self, other: Author) {
+        self.channel_id = self.channel_id.take().or(other.channel_id);
+        self.handle = self.handle.take().or(other.handle);
+        self.url = self.url.take().or(other.url);
+        self.avatar = self.avatar.take().or(other.avatar);
+        for name in other.names {
+            if !self.names.contains(&name) {
+                self.names.push(name);
+            }
+        }
+        self.comments += other.comments;
+    }
+}
+
+/// Commenters of `posts` deduped by channel id or handle, most comments first.
+pub fn index(posts: &[PostWithMeta]) -> Vec<Author> {
+    fn visit<'a>(thread: &'a CommentThread, out: &mut Vec<&'a Comment>) {
+        out.push(&thread.comment);
+        for reply in &thread.replies {
+            visit(reply, out);
+        }
+    }
+
+    // authors merged into an earlier one are left `None`, with the indices of the posts they commented on
+    let mut authors = Vec::<Option<(Author, HashSet<usize>)>>::new();
+    let mut keys = HashMap::<Key, usize>::new();
+    for (post_index, post) in posts.iter().enumerate() {
+        let mut comments = Vec::new();
+        for thread in post.post.comments.iter().flatten() {
+            visit(thread, &mut comments);
+        }
+
+        for comment in comments {
+            let comment_keys = Key::of(comment);
+            let mut found = comment_keys
+                .iter()
+                .filter_map(|key| keys.get(key).copied())
+                .collect::<Vec<_>>();
+            found.sort_unstable();
+            found.dedup();
+            let i = match found[..] {
+                [] => {
+                    authors.push(Some((Author::default(), HashSet::new())));
+                    authors.len() - 1
+                }
+                [i, ref rest @ ..] => {
+                    // the comment's channel id and handle were seen on different authors, which are the same one
+                    for &other in rest {
+                        let (other_author, other_posts) = authors[other].take().unwrap();
+                        let (author, posts) = authors[i].as_mut().unwrap();
+                        author.merge(other_author);
+                        posts.extend(other_posts);
+                        for index in keys.values_mut().filter(|index| **index == other) {
+                            *index = i;
+                        }
+                    }
+                    i
+                }
+            };
+
+            let (author, commented) = authors[i].as_mut().unwrap();
+            let channel = &comment.author_channel;
+            author.merge(Author {
+                channel_id: channel.channel_id.clone(),
+                handle: channel.handle.clone(),
+                url: channel.url.clone(),
+                names: vec![comment.author.clone()],
+                avatar: channel.avatar.clone(),
+                comments: 1,
+                posts: 0,
+            });
+            commented.insert(post_index);
+            for key in comment_keys {
+                keys.insert(key, i);
+            }
+        }
+    }
+
+    let mut authors = authors
+        .into_iter()
+        .flatten()
+        .map(|(author, posts)| Author {
+            posts: posts.len(),
+            ..author
+        })
+        .collect::<Vec<_>>();
+    // stable, authors with the same count stay in order of appearance
+    authors.sort_by_key(|author| std::cmp::Reverse(author.comments));
+    authors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        AuthorChannel,
+        tests::{comment, post},
+    };
+
+    #[test]
+    fn test_index() {
+        let mut a = post("a");
+        let mut renamed = comment("Old Name", "hi", vec![]);
+        renamed.comment.author_channel = AuthorChannel::new(
+            Some("/channel/UCx".to_owned()),
+            "Old Name",
+            Some("https://yt3.ggpht.com/x=s88".to_owned()),
+        );
+        let mut x = comment("@x", "reply", vec![]);
+        x.comment.author_channel.channel_id = Some("UCx".to_owned());
+        let mut anonymous = comment("Someone", "bye", vec![]);
+        anonymous.comment.author_channel = AuthorChannel::default();
+        a.post.comments = Some(vec![
+            renamed,
+            comment("@y", "thread", vec![x, comment("@y", "again", vec![])]),
+            anonymous,
+        ]);
+        let mut b = post("b");
+        b.post.comments = Some(vec![comment("@x", "later", vec![])]);
+
+        // seen by handle and by channel id apart, then both on one comment
+        let mut c = post("c");
+        let mut by_id = comment("Zed", "first", vec![]);
+        by_id.comment.author_channel =
+            AuthorChannel::new(Some("/channel/UCz".to_owned()), "Zed", None);
+        let mut both = comment("@z", "linked", vec![]);
+        both.comment.author_channel.channel_id = Some("UCz".to_owned());
+        c.post.comments = Some(vec![comment("@z", "by handle", vec![]), by_id, both]);
+        let mut d = post("d");
+        d.post.comments = Some(vec![comment("@z", "after", vec![])]);
+
+        let authors = index(&[a, b, c, d, post("e")]);
+        let summary = authors
+            .iter()
+            .map(|a| (a.names.join(", "), a.comments, a.posts))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("@z, Zed".to_owned(), 4, 2),
+                ("Old Name, @x".to_owned(), 3, 2),
+                ("@y".to_owned(), 2, 1),
+                ("Someone".to_owned(), 1, 1),
+            ]
+        );
+        let z = &authors[0];
+        assert_eq!(z.channel_id.as_deref(), Some("UCz"));
+        assert_eq!(z.handle.as_deref(), Some("@z"));
+        let x = &authors[1];
+        assert_eq!(x.channel_id.as_deref(), Some("UCx"));
+        assert_eq!(x.handle.as_deref(), Some("@x"));
+        assert_eq!(x.url.as_deref(), Some("/channel/UCx"));
+        assert_eq!(x.avatar.as_deref(), Some("https://yt3.ggpht.com/x=s88"));
+        assert_eq!(authors[3].handle, None);
+    }
+}
@@ -0,0 +1,342 @@
+use color_eyre::{Result, eyre::Context};
+use serde_json::Value;
+use std::{fmt::Write, fs, path::Path};
+
+use super::LocalImages;
+use crate::{
+    cmd::images::Manifest,
+    model::{AuthorBadge, CommentThread, PollAttachment, PostWithMeta},
+    rich_text::{absolute_url, encode_url, escape_markdown, href},
+};
+
+/// Write `<id>.md` for every post into `dir`, with the downloaded images in `manifest` copied into `dir/images`.
+/// Returns the number of images copied.
+pub fn write(
+    posts: &[PostWithMeta],
+    manifest: &Manifest,
+    images_dir: &Path,
+    dir: &Path,
+) -> Result<usize> {
+    let images = LocalImages::copy(manifest, images_dir, &dir.join("images"))?;
+    for post in posts {
+        let path = dir.join(format!("{}.md", post.post.id));
+        fs::write(&path, post_markdown(post, &images))
+            .wrap_err_with(|| format!("fail to write {}", path.display()))?;
+    }
+    Ok(images.len())
+}
+
+fn post_markdown(post: &PostWithMeta, images: &LocalImages<'_>) -> String {
+    let post = &post.post;
+    let main = &post.main;
+    let mut out = String::new();
+
+    // JSON strings and nulls are valid YAML
+    let optional = |value: Option<String>| value.map_or("null".to_owned(), |v| quote(&v));
+    out.push_str("---\n");
+    writeln!(out, "id: {}", quote(&post.id)).unwrap();
+    writeln!(out, "author: {}", quote(&main.author)).unwrap();
+    writeln!(out, "publish_time: {}", quote(&main.publish_time.text)).unwrap();
+    writeln!(
+        out,
+        "published_earliest: {}",
+        optional(main.publish_time.earliest.map(|t| t.to_string()))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "published_latest: {}",
+        optional(main.publish_time.latest.map(|t| t.to_string()))
+    )
+    .unwrap();
+    writeln!(out, "likes: {}", main.like).unwrap();
+    writeln!(out, "sponsor_only: {}", main.sponsor_only.is_some()).unwrap();
+    writeln!(
+        out,
+        "sponsor_tier: {}",
+        optional(main.sponsor_only.as_ref().and_then(|s| s.tier.clone()))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "total_comment: {}",
+        post.total_comment
+            .map_or("null".to_owned(), |total| total.to_string())
+    )
+    .unwrap();
+    out.push_str("---\n\n");
+
+    out.push_str(&main.content.to_markdown());
+    out.push('\n');
+
+    if let Some(attachment) = &main.content_attachment {
+        if !attachment.images.is_empty() {
+            out.push('\n');
+            for url in &attachment.images {
+                writeln!(out, "![]({})", images.src(url, "")).unwrap();
+            }
+        }
+        if !attachment.links.is_empty() {
+            out.push('\n');
+            for link in &attachment.links {
+                match href(&link.url, &link.target) {
+                    Some(href) => {
+                        writeln!(out, "- [{}]({})", escape_markdown(&href), encode_url(&href))
+                            .unwrap()
+                    }
+                    None => writeln!(out, "- {}", escape_markdown(&link.url)).unwrap(),
+                }
+            }
+        }
+    }
+    if let Some(poll) = &main.poll_attachment {
+        out.push('\n');
+        poll_table(&mut out, poll, images);
+    }
+
+    out.push_str("\n## Comments\n\n");
+    match &post.comments {
+        Some(threads) if !threads.is_empty() => {
+            for thread in threads {
+                for line in thread_lines(thread) {
+                    quote_line(&mut out, &line);
+                }
+                out.push('\n');
+            }
+        }
+        Some(_) => out.push_str("No comments.\n"),
+        None => out.push_str("Comments are turned off.\n"),
+    }
+    out
+}
+
+fn poll_table(out: &mut String, poll: &PollAttachment, images: &LocalImages<'_>) {
+    out.push_str("| Option | Result | Estimated votes |\n");
+    out.push_str("| --- | --- | --- |\n");
+    for item in &poll.items {
+        let image = item
+            .image
+            .as_deref()
+            .map(|url| format!("![]({}) ", images.src(url, "")))
+            .unwrap_or_default();
+        let selected = if item.selected { " (voted)" } else { "" };
+        let votes = item
+            .estimated_votes
+            .map(|votes| votes.to_string())
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "| {image}{}{selected} | {} | {votes} |",
+            escape_markdown(&item.text),
+            escape_markdown(&item.percentage)
+        )
+        .unwrap();
+    }
+    writeln!(out, "\n{} votes", poll.total_votes).unwrap();
+}
+
+/// Lines of a comment and its replies, each reply nested one blockquote deeper.
+fn thread_lines(thread: &CommentThread) -> Vec<String> {
+    let comment = &thread.comment;
+    let mut header = String::new();
+    if comment.pinned {
+        header.push_str("📌 Pinned · ");
+    }
+    write!(header, "**{}**", escape_markdown(&comment.author)).unwrap();
+    match comment.author_badge {
+        Some(AuthorBadge::Verified) => header.push_str(" ✓"),
+        Some(AuthorBadge::Artist) => header.push_str(" ♪"),
+        None => {}
+    }
+    if comment.channel_owner {
+        header.push_str(" (channel owner)");
+    }
+    if let Some(duration) = &comment.sponsor_duration {
+        write!(header, " ({})", escape_markdown(&duration.text)).unwrap();
+    }
+    write!(
+        header,
+        " · [{}]({})",
+        escape_markdown(&comment.publish_time.text),
+        encode_url(&absolute_url(&comment.url))
+    )
+    .unwrap();
+    if comment.like > 0 {
+        write!(header, " · {} likes", comment.like).unwrap();
+    }
+    if comment.hearted {
+        header.push_str(" · ❤ by the channel owner");
+    }
+
+    let mut lines = vec![header, String::new()];
+    lines.extend(comment.content.to_markdown().lines().map(str::to_owned));
+    for reply in &thread.replies {
+        lines.push(String::new());
+        for line in thread_lines(reply) {
+            let mut quoted = String::new();
+            quote_line(&mut quoted, &line);
+            lines.push(quoted.trim_end_matches('\n').to_owned());
+        }
+    }
+    lines
+}
+
+fn quote_line(out: &mut String, line: &str) {
+    if line.is_empty() {
+        out.push_str(">\n");
+    } else if line.starts_with('>') {
+        writeln!(out, ">{line}").unwrap();
+    } else {
+        writeln!(out, "> {line}").unwrap();
+    }
+}
+
+fn quote(s: &str) -> String {
+    Value::from(s).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cmd::images::ManifestEntry,
+        locale::Locale,
+        membership::{SponsorDuration, SponsorOnly},
+        model::{
+            ContentAttachment, PollItem,
+            tests::{comment, post},
+        },
+        rich_text::Style,
+    };
+
+    #[test]
+    fn test_write() {
+        let mut a = post("a");
+        a.post.main.sponsor_only = Some(SponsorOnly::parse(
+            "Members only (Night Sky)".to_owned(),
+            Locale::En,
+        ));
+        a.post.main.content.push_text("hi\nall *", Style::default());
+        a.post.main.content_attachment = Some(ContentAttachment {
+            images: vec![
+                "https://yt3.ggpht.com/img1=s640?q=1".to_owned(),
+                "https://yt3.ggpht.com/img2=s640".to_owned(),
+                "https://example.com/a>b (1).png".to_owned(),
+            ],
+            links: vec![],
+        });
+        a.post.main.poll_attachment = Some(PollAttachment {
+            total_votes: 10,
+            items: vec![
+                PollItem {
+                    text: "yes | no".to_owned(),
+                    percentage: "60%".to_owned(),
+                    percentage_value: Some(60.0),
+                    estimated_votes: Some(6),
+                    image: None,
+                    selected: true,
+                },
+                PollItem {
+                    text: "no".to_owned(),
+                    percentage: "40%".to_owned(),
+                    percentage_value: Some(40.0),
+                    estimated_votes: Some(4),
+                    image: None,
+                    selected: false,
+                },
+            ],
+        });
+        let mut x = comment(
+            "@x",
+            "thank you",
+            vec![comment(
+                "@y",
+                "reply\nline",
+                vec![comment("@z", "deeper", vec![])],
+            )],
+        );
+        x.comment.sponsor_duration = Some(SponsorDuration::parse(
+            "Member for 2 months".to_owned(),
+            Locale::En,
+        ));
+        x.comment.like = 3;
+        x.comment.pinned = true;
+        x.comment.hearted = true;
+        let reply = &mut x.replies[0].comment;
+        reply.channel_owner = true;
+        reply.author_badge = Some(AuthorBadge::Verified);
+        a.post.comments = Some(vec![x, comment("@w", "bye", vec![])]);
+        a.post.total_comment = Some(4);
+
+        let images_dir = tempfile::tempdir().unwrap();
+        fs::write(images_dir.path().join("img1%3Ds640"), "1").unwrap();
+        let manifest = Manifest::from([(
+            "https://yt3.ggpht.com/img1=s640".to_owned(),
+            ManifestEntry {
+                file: "img1%3Ds640".to_owned(),
+                source: None,
+                blake3: String::new(),
+                size: 1,
+            },
+        )]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let copied = write(&[a, post("b")], &manifest, images_dir.path(), dir.path()).unwrap();
+        assert_eq!(copied, 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("images/img1%3Ds640")).unwrap(),
+            "1"
+        );
+        assert!(
+            fs::read_to_string(dir.path().join("b.md"))
+                .unwrap()
+                .ends_with("## Comments\n\nComments are turned off.\n")
+        );
+        insta::assert_snapshot!(fs::read_to_string(dir.path().join("a.md")).unwrap(), @r#"
+        ---
+        id: "a"
+        author: "author"
+        publish_time: "1 ปีที่แล้ว"
+        published_earliest: null
+        published_latest: null
+        likes: 1
+        sponsor_only: true
+        sponsor_tier: "Night Sky"
+        total_comment: 4
+        ---
+
+        hi  
+        all \*
+
+        ![](images/img1%253Ds640)
+        ![](https://yt3.ggpht.com/img2=s640)
+        ![](https://example.com/a%3Eb%20%281%29.png)
+
+        | Option | Result | Estimated votes |
+        | --- | --- | --- |
+        | yes \| no (voted) | 60% | 6 |
+        | no | 40% | 4 |
+
+        10 votes
+
+        ## Comments
+
+        > 📌 Pinned · **@x** (Member for 2 months) · [1 ปีที่แล้ว](https://www.youtube.com/post/a?lc=Ugz) · 3 likes · ❤ by the channel owner
+        >
+        > thank you
+        >
+        >> **@y** ✓ (channel owner) · [1 ปีที่แล้ว](https://www.youtube.com/post/a?lc=Ugz)
+        >>
+        >> reply  
+        >> line
+        >>
+        >>> **@z** · [1 ปีที่แล้ว](https://www.youtube.com/post/a?lc=Ugz)
+        >>>
+        >>> deeper
+
+        > **@w** · [1 ปีที่แล้ว](https://www.youtube.com/post/a?lc=Ugz)
+        >
+        > bye
+        "#);
+    }
+}
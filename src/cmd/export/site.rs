@@ -0,0 +1,616 @@
+use color_eyre::{
+    Result,
+    eyre::{Context, ensure},
+};
+use std::{
+    collections::BTreeSet,
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{LocalImages, copy_if_changed};
+use crate::{
+    cmd::images::{Manifest, percent_encode},
+    link::YOUTUBE_ORIGIN,
+    model::{Archive, AuthorBadge, CommentThread, PollAttachment, PostWithMeta},
+    publish_time::PublishTime,
+    rich_text::{RichText, absolute_url, escape_html, href},
+};
+
+const STYLE: &str = r#"body { margin: 0; font-family: sans-serif; background: #f9f9f9; color: #0f0f0f; }
+body > header { padding: 12px 16px; background: #fff; border-bottom: 1px solid #ddd; font-weight: bold; }
+body > header a { color: inherit; text-decoration: none; }
+main { max-width: 720px; margin: 0 auto; padding: 16px; }
+a { color: #065fd4; }
+.post { background: #fff; border: 1px solid #ddd; border-radius: 12px; padding: 16px; margin-bottom: 16px; }
+.post > header, .comment > header { display: flex; gap: 8px; align-items: center; flex-wrap: wrap; }
+.author { font-weight: bold; }
+.time, .likes, .votes { color: #606060; font-size: 0.9em; }
+.sponsor-only { background: #2ba640; color: #fff; border-radius: 4px; padding: 0 6px; font-size: 0.8em; }
+.content { margin: 8px 0; white-space: normal; overflow-wrap: anywhere; }
+.emote { width: 24px; height: 24px; vertical-align: middle; }
+.images { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 4px; }
+.images img { width: 100%; border-radius: 8px; }
+.poll { list-style: none; padding: 0; }
+.poll li { position: relative; border: 1px solid #ddd; border-radius: 4px; margin: 4px 0; padding: 6px 8px; display: flex; gap: 8px; align-items: center; }
+.poll li.selected { border-color: #065fd4; }
+.poll img { width: 64px; border-radius: 4px; }
+.poll .percentage { margin-left: auto; }
+.poll .bar { position: absolute; left: 0; top: 0; bottom: 0; background: #0000000d; }
+.post > footer { display: flex; gap: 16px; color: #606060; font-size: 0.9em; }
+.comments ol { list-style: none; padding-left: 0; }
+.comments ol ol { padding-left: 32px; }
+.comment { margin: 12px 0; }
+.badge { width: 16px; height: 16px; }
+.owner > header .author { background: #888; color: #fff; border-radius: 12px; padding: 0 8px; }
+.pinned-label, .author-badge { color: #606060; font-size: 0.9em; }
+.hearted { color: #e00; margin-left: 8px; }
+.pagination { display: flex; gap: 8px; justify-content: center; flex-wrap: wrap; margin: 16px 0; }
+.pagination .current { font-weight: bold; }
+"#;
+
+/// Downloaded images to show in place of their urls, images not downloaded are linked to their origin.
+pub struct Assets {
+    /// Directory of the `images` command.
+    pub images_dir: PathBuf,
+    /// Downloaded images by url, from `manifest.json` in the images dir.
+    pub images: Manifest,
+    /// Directory of `emotes sync`, containing `<name>.png`.
+    pub emote_dir: PathBuf,
+}
+
+/// What was written by [`write`].
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub pages: usize,
+    pub images: usize,
+    pub emotes: usize,
+}
+
+/// Write a static site of `archive` into `dir`: `index.html` and `page-<n>.html` listing `per_page` posts each,
+/// `post/<id>.html` with the comments of every post, and the downloaded images and emotes they use.
+pub fn write(archive: &Archive, assets: &Assets, per_page: usize, dir: &Path) -> Result<Summary> {
+    ensure!(per_page > 0, "posts per page must be at least 1");
+    for sub in ["post", "emotes"] {
+        fs::create_dir_all(dir.join(sub))
+            .wrap_err_with(|| format!("fail to create {}", dir.join(sub).display()))?;
+    }
+    fs::write(dir.join("style.css"), STYLE)?;
+
+    let images = LocalImages::copy(&assets.images, &assets.images_dir, &dir.join("images"))?;
+    let mut emotes = BTreeSet::new();
+    match fs::read_dir(&assets.emote_dir) {
+        Ok(entries) => {
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "png")
+                    && let Some(name) = path.file_stem().and_then(|name| name.to_str())
+                {
+                    copy_if_changed(&path, &dir.join("emotes").join(path.file_name().unwrap()))?;
+                    emotes.insert(name.to_owned());
+                }
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err)
+                .wrap_err_with(|| format!("fail to read {}", assets.emote_dir.display()));
+        }
+    }
+
+    let site = Site {
+        title: archive
+            .channel
+            .as_ref()
+            .map_or("Community posts", |channel| &channel.name),
+        images,
+        emotes,
+    };
+    let posts = &archive.posts;
+    let pages = posts.chunks(per_page).collect::<Vec<_>>();
+    let page_count = pages.len().max(1);
+    for n in 1..=page_count {
+        let page_posts = pages.get(n - 1).copied().unwrap_or_default();
+        fs::write(
+            dir.join(page_file(n)),
+            site.index(page_posts, n, page_count),
+        )?;
+    }
+    for (i, post) in posts.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|i| &posts[i]);
+        let next = posts.get(i + 1);
+        let html = site.post(post, i / per_page + 1, prev, next);
+        fs::write(
+            dir.join("post").join(format!("{}.html", post.post.id)),
+            html,
+        )?;
+    }
+
+    Ok(Summary {
+        pages: page_count,
+        images: site.images.len(),
+        emotes: site.emotes.len(),
+    })
+}
+
+fn page_file(n: usize) -> String {
+    if n == 1 {
+        "index.html".to_owned()
+    } else {
+        format!("page-{n}.html")
+    }
+}
+
+struct Site<'a> {
+    title: &'a str,
+    /// Images copied into `images/`.
+    images: LocalImages<'a>,
+    /// Names of emotes in `emotes/`.
+    emotes: BTreeSet<String>,
+}
+
+impl Site<'_> {
+    /// `root` is the relative path from the page to the site root, ie. `../` for post pages.
+    fn layout(&self, title: &str, root: &str, body: &str) -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{}</title>
+<link rel="stylesheet" href="{root}style.css">
+</head>
+<body>
+<header><a href="{root}index.html">{}</a></header>
+<main>
+{body}</main>
+</body>
+</html>
+"#,
+            escape_html(title),
+            escape_html(self.title),
+        )
+    }
+
+    fn index(&self, posts: &[PostWithMeta], n: usize, page_count: usize) -> String {
+        let mut body = String::new();
+        if posts.is_empty() {
+            body.push_str("<p>No posts</p>\n");
+        }
+        for post in posts {
+            self.post_card(&mut body, post, "");
+        }
+        pagination(&mut body, n, page_count);
+        let title = if n == 1 {
+            self.title.to_owned()
+        } else {
+            format!("{} - page {n}", self.title)
+        };
+        self.layout(&title, "", &body)
+    }
+
+    fn post(
+        &self,
+        post: &PostWithMeta,
+        page: usize,
+        prev: Option<&PostWithMeta>,
+        next: Option<&PostWithMeta>,
+    ) -> String {
+        let root = "../";
+        let mut body = String::new();
+        self.post_card(&mut body, post, root);
+
+        body.push_str(r#"<section id="comments" class="comments">"#);
+        match &post.post.comments {
+            Some(threads) => {
+                let total = post.post.total_comment.unwrap_or_default();
+                writeln!(body, "<h2>{total} comments</h2>").unwrap();
+                if threads.len() < total as usize {
+                    body.push_str(
+                        "<p>Only the comments loaded when the post was saved are archived.</p>\n",
+                    );
+                }
+                self.threads(&mut body, threads, root);
+            }
+            None => body.push_str("<p>Comments are turned off.</p>\n"),
+        }
+        body.push_str("</section>\n");
+
+        body.push_str(r#"<nav class="pagination">"#);
+        if let Some(prev) = prev {
+            write!(
+                body,
+                r#"<a rel="prev" href="{}.html">Previous post</a>"#,
+                prev.post.id
+            )
+            .unwrap();
+        }
+        write!(
+            body,
+            r#"<a href="{root}{}">Page {page}</a>"#,
+            page_file(page)
+        )
+        .unwrap();
+        if let Some(next) = next {
+            write!(
+                body,
+                r#"<a rel="next" href="{}.html">Next post</a>"#,
+                next.post.id
+            )
+            .unwrap();
+        }
+        body.push_str("</nav>\n");
+
+        let title = format!(
+            "{} - {}",
+            post.post.main.author,
+            post.post.main.content.to_plain()
+        );
+        let title = title.chars().take(80).collect::<String>();
+        self.layout(&title, root, &body)
+    }
+
+    fn post_card(&self, out: &mut String, post: &PostWithMeta, root: &str) {
+        let post = &post.post;
+        let main = &post.main;
+        let permalink = format!("{root}post/{}.html", post.id);
+
+        write!(out, r#"<article class="post" id="{}">"#, post.id).unwrap();
+        write!(
+            out,
+            r#"<header><span class="author">{}</span><a class="time" href="{permalink}">{}</a>"#,
+            escape_html(&main.author),
+            publish_time(&main.publish_time),
+        )
+        .unwrap();
+        if let Some(sponsor_only) = &main.sponsor_only {
+            write!(
+                out,
+                r#"<span class="sponsor-only">{}</span>"#,
+                escape_html(&sponsor_only.text)
+            )
+            .unwrap();
+        }
+        out.push_str("</header>\n");
+        writeln!(
+            out,
+            "<div class=\"content\">{}</div>",
+            self.content(&main.content, root)
+        )
+        .unwrap();
+
+        if let Some(attachment) = &main.content_attachment {
+            if !attachment.images.is_empty() {
+                out.push_str(r#"<div class="images">"#);
+                for url in &attachment.images {
+                    let src = self.image_src(url, root);
+                    write!(
+                        out,
+                        r#"<a href="{src}"><img src="{src}" loading="lazy" alt=""></a>"#
+                    )
+                    .unwrap();
+                }
+                out.push_str("</div>\n");
+            }
+            if !attachment.links.is_empty() {
+                out.push_str(r#"<ul class="links">"#);
+                for link in &attachment.links {
+                    match href(&link.url, &link.target) {
+                        Some(href) => write!(
+                            out,
+                            r#"<li><a href="{}">{}</a></li>"#,
+                            escape_html(&href),
+                            escape_html(&href)
+                        )
+                        .unwrap(),
+                        None => write!(out, "<li>{}</li>", escape_html(&link.url)).unwrap(),
+                    }
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+        if let Some(poll) = &main.poll_attachment {
+            self.poll(out, poll, root);
+        }
+
+        write!(
+            out,
+            r#"<footer><span class="likes">{} likes</span>"#,
+            main.like
+        )
+        .unwrap();
+        if let Some(total) = post.total_comment {
+            write!(
+                out,
+                r##"<a href="{permalink}#comments">{total} comments</a>"##
+            )
+            .unwrap();
+        }
+        write!(
+            out,
+            r#"<a href="{YOUTUBE_ORIGIN}/post/{}">Original</a></footer>"#,
+            post.id
+        )
+        .unwrap();
+        out.push_str("</article>\n");
+    }
+
+    fn poll(&self, out: &mut String, poll: &PollAttachment, root: &str) {
+        out.push_str(r#"<ol class="poll">"#);
+        for item in &poll.items {
+            let class = if item.selected {
+                r#" class="selected""#
+            } else {
+                ""
+            };
+            write!(out, "<li{class}>").unwrap();
+            if let Some(value) = item.percentage_value {
+                write!(out, r#"<span class="bar" style="width: {value}%"></span>"#).unwrap();
+            }
+            if let Some(image) = &item.image {
+                write!(
+                    out,
+                    r#"<img src="{}" loading="lazy" alt="">"#,
+                    self.image_src(image, root)
+                )
+                .unwrap();
+            }
+            write!(
+                out,
+                r#"<span class="text">{}</span><span class="percentage">{}</span></li>"#,
+                escape_html(&item.text),
+                escape_html(&item.percentage)
+            )
+            .unwrap();
+        }
+        out.push_str("</ol>\n");
+        writeln!(out, "<p class=\"votes\">{} votes</p>", poll.total_votes).unwrap();
+    }
+
+    fn threads(&self, out: &mut String, threads: &[CommentThread], root: &str) {
+        out.push_str("<ol>\n");
+        for thread in threads {
+            let comment = &thread.comment;
+            let class = match (comment.pinned, comment.channel_owner) {
+                (true, true) => "comment pinned owner",
+                (true, false) => "comment pinned",
+                (false, true) => "comment owner",
+                (false, false) => "comment",
+            };
+            write!(out, r#"<li class="{class}"><header>"#).unwrap();
+            if comment.pinned {
+                out.push_str(r#"<span class="pinned-label">Pinned</span>"#);
+            }
+            write!(
+                out,
+                r#"<span class="author">{}</span>"#,
+                escape_html(&comment.author)
+            )
+            .unwrap();
+            match comment.author_badge {
+                Some(AuthorBadge::Verified) => {
+                    out.push_str(r#"<span class="author-badge" title="Verified">✓</span>"#)
+                }
+                Some(AuthorBadge::Artist) => out.push_str(
+                    r#"<span class="author-badge" title="Official artist channel">♪</span>"#,
+                ),
+                None => {}
+            }
+            if let Some(badge) = &comment.sponsor_badge {
+                write!(
+                    out,
+                    r#"<img class="badge" src="{}" alt="" title="{}">"#,
+                    self.image_src(badge, root),
+                    escape_html(
+                        comment
+                            .sponsor_duration
+                            .as_ref()
+                            .map_or("", |duration| &duration.text)
+                    )
+                )
+                .unwrap();
+            }
+            write!(
+                out,
+                r#"<a class="time" href="{}">{}</a></header>"#,
+                escape_html(&absolute_url(&comment.url)),
+                publish_time(&comment.publish_time)
+            )
+            .unwrap();
+            write!(
+                out,
+                "\n<div class=\"content\">{}</div>\n",
+                self.content(&comment.content, root)
+            )
+            .unwrap();
+            if comment.like > 0 {
+                writeln!(out, "<span class=\"likes\">{} likes</span>", comment.like).unwrap();
+            }
+            if comment.hearted {
+                out.push_str(
+                    "<span class=\"hearted\" title=\"Hearted by the channel owner\">❤</span>\n",
+                );
+            }
+            if !thread.replies.is_empty() {
+                self.threads(out, &thread.replies, root);
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ol>\n");
+    }
+
+    fn content(&self, content: &RichText, root: &str) -> String {
+        content.to_html_with(|name| {
+            self.emotes
+                .contains(name)
+                .then(|| format!("{root}emotes/{}.png", percent_encode(name)))
+        })
+    }
+
+    /// Escaped src of an image, the local copy if downloaded.
+    fn image_src(&self, url: &str, root: &str) -> String {
+        escape_html(&self.images.src(url, root))
+    }
+}
+
+fn publish_time(time: &PublishTime) -> String {
+    match time.earliest.zip(time.latest) {
+        Some((earliest, latest)) => format!(
+            r#"<time datetime="{latest}" title="{} – {}">{}</time>"#,
+            earliest.strftime("%Y-%m-%d %H:%M UTC"),
+            latest.strftime("%Y-%m-%d %H:%M UTC"),
+            escape_html(&time.text)
+        ),
+        None => escape_html(&time.text),
+    }
+}
+
+fn pagination(out: &mut String, n: usize, page_count: usize) {
+    if page_count == 1 {
+        return;
+    }
+    out.push_str(r#"<nav class="pagination">"#);
+    if n > 1 {
+        write!(
+            out,
+            r#"<a rel="prev" href="{}">Previous</a>"#,
+            page_file(n - 1)
+        )
+        .unwrap();
+    }
+    for i in 1..=page_count {
+        if i == n {
+            write!(out, r#"<span class="current">{i}</span>"#).unwrap();
+        } else {
+            write!(out, r#"<a href="{}">{i}</a>"#, page_file(i)).unwrap();
+        }
+    }
+    if n < page_count {
+        write!(out, r#"<a rel="next" href="{}">Next</a>"#, page_file(n + 1)).unwrap();
+    }
+    out.push_str("</nav>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cmd::images::ManifestEntry,
+        link::LinkTarget,
+        model::{
+            ContentAttachment, Link,
+            tests::{comment, post},
+        },
+        rich_text::{Span, Style},
+    };
+
+    #[test]
+    fn test_write() {
+        let mut a = post("a");
+        a.post.main.content.push_text("hi <all> ", Style::default());
+        a.post.main.content.0.push(Span::Emote {
+            name: Some("heart".to_owned()),
+            url: "https://yt3.ggpht.com/abc=w24-h24-c-k-nd".to_owned(),
+        });
+        a.post.main.content_attachment = Some(ContentAttachment {
+            images: vec![
+                "https://yt3.ggpht.com/img1=s640?q=1".to_owned(),
+                "https://yt3.ggpht.com/img2=s640".to_owned(),
+            ],
+            links: ["https://example.com/a", "javascript:alert(1)"]
+                .map(|url| Link {
+                    target: LinkTarget::classify(url),
+                    url: url.to_owned(),
+                })
+                .into(),
+        });
+        a.post.comments = Some(vec![comment(
+            "@x",
+            "thank you :_heart:",
+            vec![comment("@y", "reply", vec![])],
+        )]);
+        a.post.total_comment = Some(2);
+        let archive = Archive::new(vec![a, post("b"), post("c")], None);
+
+        let src = tempfile::tempdir().unwrap();
+        let images_dir = src.path().join("archive_imgs");
+        let emote_dir = src.path().join("emote");
+        fs::create_dir_all(&images_dir).unwrap();
+        fs::create_dir_all(&emote_dir).unwrap();
+        fs::write(images_dir.join("img1%3Ds640"), "1").unwrap();
+        fs::write(emote_dir.join("heart.png"), "heart").unwrap();
+        let entry = |file: &str| ManifestEntry {
+            file: file.to_owned(),
+            source: None,
+            blake3: String::new(),
+            size: 1,
+        };
+        let assets = Assets {
+            images_dir,
+            images: Manifest::from([
+                (
+                    "https://yt3.ggpht.com/img1=s640".to_owned(),
+                    entry("img1%3Ds640"),
+                ),
+                // listed but missing from the images dir
+                (
+                    "https://yt3.ggpht.com/img2=s640".to_owned(),
+                    entry("img2%3Ds640"),
+                ),
+            ]),
+            emote_dir,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let summary = write(&archive, &assets, 2, dir.path()).unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                pages: 2,
+                images: 1,
+                emotes: 1
+            }
+        );
+        let read = |path: &str| fs::read_to_string(dir.path().join(path)).unwrap();
+        assert_eq!(read("images/img1%3Ds640"), "1");
+        assert_eq!(read("emotes/heart.png"), "heart");
+
+        let index = read("index.html");
+        assert!(
+            index.contains(r#"<a class="time" href="post/a.html">"#),
+            "{index}"
+        );
+        assert!(index.contains(r#"<link rel="stylesheet" href="style.css">"#));
+        assert!(index.contains(r#"<a rel="next" href="page-2.html">Next</a>"#));
+        assert!(index.contains(r##"<a href="post/a.html#comments">2 comments</a>"##));
+        assert!(!index.contains(r#"id="c""#));
+        let page_2 = read("page-2.html");
+        assert!(page_2.contains(r#"id="c""#));
+        assert!(page_2.contains(r#"<a rel="prev" href="index.html">Previous</a>"#));
+
+        let page = read("post/a.html");
+        assert!(page.contains(
+            r#"hi &lt;all&gt; <img class="emote" src="../emotes/heart.png" alt=":_heart:">"#
+        ));
+        assert!(page.contains(r#"<img src="../images/img1%253Ds640" loading="lazy" alt="">"#));
+        assert!(page.contains(r#"<img src="https://yt3.ggpht.com/img2=s640" loading="lazy""#));
+        assert!(page.contains(
+            r#"<li><a href="https://example.com/a">https://example.com/a</a></li><li>javascript:alert(1)</li>"#
+        ));
+        assert!(
+            page.contains(
+                r#"thank you <img class="emote" src="../emotes/heart.png" alt=":_heart:">"#
+            )
+        );
+        // reply nested in its thread
+        let (thread, _) = page
+            .split_once(r#"<span class="author">@y</span>"#)
+            .unwrap();
+        assert_eq!(thread.matches("<ol>").count(), 2);
+        assert!(page.contains(r#"<a rel="next" href="b.html">Next post</a>"#));
+        assert!(read("post/b.html").contains("Comments are turned off."));
+        assert!(read("post/c.html").contains(r#"<a href="../page-2.html">Page 2</a>"#));
+    }
+}
@@ -0,0 +1,381 @@
+use color_eyre::{Result, eyre::Context};
+use rusqlite::{Connection, Transaction, params};
+use serde_json::Value;
+use std::{fs, path::Path};
+
+use crate::{
+    emote::emote_id,
+    model::{CommentThread, PostWithMeta},
+    publish_time::PublishTime,
+    rich_text::{RichText, Span},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE posts (
+    id TEXT PRIMARY KEY,
+    -- order in posts.json
+    position INTEGER NOT NULL,
+    author TEXT NOT NULL,
+    author_channel_id TEXT,
+    author_handle TEXT,
+    author_avatar TEXT,
+    publish_time TEXT NOT NULL,
+    published_earliest TEXT,
+    published_latest TEXT,
+    -- members-only badge text, NULL for public posts
+    sponsor_only TEXT,
+    sponsor_tier TEXT,
+    -- plain text, spans are in content_json
+    content TEXT NOT NULL,
+    content_json TEXT NOT NULL,
+    like_count INTEGER NOT NULL,
+    -- NULL if comments are turned off
+    total_comment INTEGER,
+    poll_total_votes INTEGER,
+    source_modified_at TEXT,
+    processed_at TEXT NOT NULL
+);
+
+CREATE TABLE comments (
+    id INTEGER PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES posts(id),
+    -- thread of a reply
+    parent_id INTEGER REFERENCES comments(id),
+    -- order among its siblings
+    position INTEGER NOT NULL,
+    author TEXT NOT NULL,
+    author_channel_id TEXT,
+    author_handle TEXT,
+    author_avatar TEXT,
+    publish_time TEXT NOT NULL,
+    published_earliest TEXT,
+    published_latest TEXT,
+    content TEXT NOT NULL,
+    content_json TEXT NOT NULL,
+    url TEXT NOT NULL,
+    -- membership badge label of the author, NULL if not a member
+    sponsor_duration TEXT,
+    membership_months INTEGER,
+    new_member INTEGER,
+    sponsor_badge TEXT,
+    like_count INTEGER NOT NULL,
+    -- signals from the channel owner, booleans
+    hearted INTEGER NOT NULL,
+    pinned INTEGER NOT NULL,
+    channel_owner INTEGER NOT NULL,
+    -- `verified` or `artist`
+    author_badge TEXT,
+    edited INTEGER NOT NULL
+);
+
+CREATE TABLE attachments (
+    id INTEGER PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES posts(id),
+    position INTEGER NOT NULL,
+    -- `image` or `link`
+    kind TEXT NOT NULL,
+    url TEXT NOT NULL,
+    -- kind of link target, ie. `video` or `external`
+    target_kind TEXT,
+    target_json TEXT
+);
+
+CREATE TABLE poll_items (
+    post_id TEXT NOT NULL REFERENCES posts(id),
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    percentage TEXT NOT NULL,
+    percentage_value REAL,
+    estimated_votes INTEGER,
+    image TEXT,
+    selected INTEGER NOT NULL,
+    PRIMARY KEY (post_id, position)
+);
+
+-- custom emotes used in posts and comments
+CREATE TABLE emotes (
+    id INTEGER PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES posts(id),
+    -- NULL if used in the post itself
+    comment_id INTEGER REFERENCES comments(id),
+    -- NULL if unresolved
+    name TEXT,
+    emote_id TEXT,
+    url TEXT NOT NULL
+);
+
+CREATE INDEX posts_published_earliest ON posts(published_earliest);
+CREATE INDEX comments_post_id ON comments(post_id);
+CREATE INDEX comments_parent_id ON comments(parent_id);
+CREATE INDEX comments_author ON comments(author);
+CREATE INDEX comments_author_handle ON comments(author_handle);
+CREATE INDEX attachments_post_id ON attachments(post_id);
+CREATE INDEX emotes_post_id ON emotes(post_id);
+CREATE INDEX emotes_comment_id ON emotes(comment_id);
+CREATE INDEX emotes_name ON emotes(name);
+
+-- full-text search, trigram tokenizer also matches inside Thai and Japanese text which have no spaces between words
+CREATE VIRTUAL TABLE posts_fts USING fts5(content, content='posts', tokenize='trigram');
+CREATE VIRTUAL TABLE comments_fts USING fts5(content, content='comments', tokenize='trigram');
+";
+
+/// Write `posts` into a new SQLite database at `path`, replacing any existing file.
+pub fn write(posts: &[PostWithMeta], path: &Path) -> Result<()> {
+    let part = path.with_extension("part");
+    if part.exists() {
+        fs::remove_file(&part)?;
+    }
+    let mut conn =
+        Connection::open(&part).wrap_err_with(|| format!("fail to create {}", part.display()))?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    for (position, post) in posts.iter().enumerate() {
+        insert_post(&tx, post, position)
+            .wrap_err_with(|| format!("fail to insert post {}", post.post.id))?;
+    }
+    tx.execute_batch(
+        "INSERT INTO posts_fts(posts_fts) VALUES('rebuild');
+         INSERT INTO comments_fts(comments_fts) VALUES('rebuild');",
+    )?;
+    tx.commit()?;
+    conn.close().map_err(|(_, err)| err)?;
+
+    fs::rename(&part, path)?;
+    Ok(())
+}
+
+fn insert_post(tx: &Transaction<'_>, post: &PostWithMeta, position: usize) -> Result<()> {
+    let PostWithMeta { meta, post } = post;
+    let main = &post.main;
+    let (publish_time, earliest, latest) = publish_time_columns(&main.publish_time);
+    tx.execute(
+        "INSERT INTO posts VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            post.id,
+            position as i64,
+            main.author,
+            main.author_channel.channel_id,
+            main.author_channel.handle,
+            main.author_channel.avatar,
+            publish_time,
+            earliest,
+            latest,
+            main.sponsor_only.as_ref().map(|s| &s.text),
+            main.sponsor_only.as_ref().and_then(|s| s.tier.as_ref()),
+            main.content.to_plain(),
+            serde_json::to_string(&main.content)?,
+            main.like,
+            post.total_comment,
+            main.poll_attachment.as_ref().map(|poll| poll.total_votes),
+            meta.source_modified_at.map(|t| t.to_string()),
+            meta.processed_at.to_string(),
+        ],
+    )?;
+    insert_emotes(tx, &post.id, None, &main.content)?;
+
+    if let Some(attachment) = &main.content_attachment {
+        let images = attachment.images.iter().map(|url| ("image", url, None));
+        let links = attachment
+            .links
+            .iter()
+            .map(|link| ("link", &link.url, Some(&link.target)));
+        for (position, (kind, url, target)) in images.chain(links).enumerate() {
+            let target = target.map(serde_json::to_value).transpose()?;
+            tx.execute(
+                "INSERT INTO attachments (post_id, position, kind, url, target_kind, target_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    post.id,
+                    position as i64,
+                    kind,
+                    url,
+                    target.as_ref().and_then(|t| t["kind"].as_str()),
+                    target.as_ref().map(|t| t.to_string()),
+                ],
+            )?;
+        }
+    }
+
+    if let Some(poll) = &main.poll_attachment {
+        for (position, item) in poll.items.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO poll_items VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    post.id,
+                    position as i64,
+                    item.text,
+                    item.percentage,
+                    item.percentage_value,
+                    item.estimated_votes,
+                    item.image,
+                    item.selected,
+                ],
+            )?;
+        }
+    }
+
+    if let Some(comments) = &post.comments {
+        insert_threads(tx, &post.id, None, comments)?;
+    }
+    Ok(())
+}
+
+fn insert_threads(
+    tx: &Transaction<'_>,
+    post_id: &str,
+    parent_id: Option<i64>,
+    threads: &[CommentThread],
+) -> Result<()> {
+    for (position, thread) in threads.iter().enumerate() {
+        let comment = &thread.comment;
+        let (publish_time, earliest, latest) = publish_time_columns(&comment.publish_time);
+        let sponsor_duration = comment.sponsor_duration.as_ref();
+        tx.execute(
+            "INSERT INTO comments (post_id, parent_id, position, author, author_channel_id, author_handle, author_avatar,
+                                   publish_time, published_earliest, published_latest,
+                                   content, content_json, url, sponsor_duration, membership_months, new_member, sponsor_badge, like_count,
+                                   hearted, pinned, channel_owner, author_badge, edited)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+            params![
+                post_id,
+                parent_id,
+                position as i64,
+                comment.author,
+                comment.author_channel.channel_id,
+                comment.author_channel.handle,
+                comment.author_channel.avatar,
+                publish_time,
+                earliest,
+                latest,
+                comment.content.to_plain(),
+                serde_json::to_string(&comment.content)?,
+                comment.url,
+                sponsor_duration.map(|d| &d.text),
+                sponsor_duration.and_then(|d| d.months),
+                sponsor_duration.map(|d| d.new_member),
+                comment.sponsor_badge,
+                comment.like,
+                comment.hearted,
+                comment.pinned,
+                comment.channel_owner,
+                comment
+                    .author_badge
+                    .map(serde_json::to_value)
+                    .transpose()?
+                    .as_ref()
+                    .and_then(Value::as_str),
+                comment.edited,
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        insert_emotes(tx, post_id, Some(id), &comment.content)?;
+        insert_threads(tx, post_id, Some(id), &thread.replies)?;
+    }
+    Ok(())
+}
+
+fn insert_emotes(
+    tx: &Transaction<'_>,
+    post_id: &str,
+    comment_id: Option<i64>,
+    content: &RichText,
+) -> Result<()> {
+    for span in &content.0 {
+        if let Span::Emote { name, url } = span {
+            tx.execute(
+                "INSERT INTO emotes (post_id, comment_id, name, emote_id, url) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![post_id, comment_id, name, emote_id(url), url],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn publish_time_columns(time: &PublishTime) -> (&str, Option<String>, Option<String>) {
+    (
+        &time.text,
+        time.earliest.map(|t| t.to_string()),
+        time.latest.map(|t| t.to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::{
+            AuthorBadge,
+            tests::{comment, post},
+        },
+        rich_text::Style,
+    };
+
+    #[test]
+    fn test_write() {
+        let mut a = post("a");
+        a.post
+            .main
+            .content
+            .push_text("ขอบคุณทุกคนมาก ", Style::default());
+        a.post.main.content.0.push(Span::Emote {
+            name: Some("heart".to_owned()),
+            url: "https://yt3.ggpht.com/abc=w24-h24-c-k-nd".to_owned(),
+        });
+        let mut z = comment("@z", "bye", vec![]);
+        z.comment.pinned = true;
+        z.comment.author_badge = Some(AuthorBadge::Artist);
+        a.post.comments = Some(vec![
+            comment("@x", "thank you", vec![comment("@y", "ありがとう", vec![])]),
+            z,
+        ]);
+        let posts = [a, post("b")];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("posts.sqlite");
+        fs::write(&path, "stale").unwrap();
+        write(&posts, &path).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let query = |sql: &str| -> Vec<String> {
+            let mut stmt = conn.prepare(sql).unwrap();
+            stmt.query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        };
+        assert_eq!(query("SELECT id FROM posts ORDER BY position"), ["a", "b"]);
+        assert_eq!(
+            query(
+                "SELECT c.author || ' <- ' || r.author FROM comments r JOIN comments c ON r.parent_id = c.id"
+            ),
+            ["@x <- @y"]
+        );
+        assert_eq!(
+            query(
+                "SELECT author || ' ' || author_badge FROM comments WHERE pinned AND NOT hearted"
+            ),
+            ["@z artist"]
+        );
+        assert_eq!(
+            query("SELECT author_handle FROM comments WHERE parent_id IS NULL ORDER BY position"),
+            ["@x", "@z"]
+        );
+        assert_eq!(
+            query("SELECT name || ' ' || emote_id FROM emotes WHERE comment_id IS NULL"),
+            ["heart abc="]
+        );
+        assert_eq!(
+            query(
+                "SELECT id FROM posts WHERE rowid IN (SELECT rowid FROM posts_fts WHERE posts_fts MATCH 'ทุกคน')"
+            ),
+            ["a"]
+        );
+        assert_eq!(
+            query(
+                "SELECT author FROM comments WHERE rowid IN (SELECT rowid FROM comments_fts WHERE comments_fts MATCH 'りがと')"
+            ),
+            ["@y"]
+        );
+    }
+}
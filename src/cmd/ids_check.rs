@@ -0,0 +1,127 @@
+use color_eyre::Result;
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use crate::cmd::Common;
+
+/// Exit code when some posts are not downloaded yet.
+const EXIT_MISSING: u8 = 2;
+/// Exit code when every post is downloaded, but the archive has posts not in post ids.
+const EXIT_EXTRA: u8 = 3;
+
+#[derive(clap::Args)]
+#[command(after_help = "Exit codes:\n  \
+    0  every post is downloaded\n  \
+    1  fail to read post ids or archive\n  \
+    2  some posts are missing (and maybe extra)\n  \
+    3  no posts are missing, but there are extra posts")]
+pub struct Args {
+    #[arg(long)]
+    /// Print missing ids as a JSON list in source order, which `--posts-file` of the downloader reads.
+    /// Extra ids are printed to stderr.
+    json: bool,
+    #[arg(long, value_name = "FILE")]
+    /// Write missing ids as a JSON list, for re-downloading with the downloader.
+    retry_file: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq)]
+struct IdsCheck<'a> {
+    /// Post ids not in the archive, in source order.
+    missing: Vec<&'a str>,
+    /// Archived posts not in post ids, sorted.
+    extra: Vec<&'a str>,
+}
+
+pub fn run(common: &Common, args: Args) -> Result<ExitCode> {
+    let post_ids = common.read_post_ids()?;
+    let archived = archived_ids(&common.archive_dir)?;
+    let result = ids_check(&post_ids, &archived);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&result.missing)?);
+        if !result.extra.is_empty() {
+            eprintln!("Extra: {:?}", result.extra);
+        }
+    } else {
+        println!("Extra: {:?}", result.extra);
+        println!("Missing: {:?}", result.missing);
+    }
+
+    if let Some(retry_file) = &args.retry_file {
+        let mut writer = BufWriter::new(File::create(retry_file)?);
+        serde_json::to_writer_pretty(&mut writer, &result.missing)?;
+        writer.flush()?;
+        eprintln!(
+            "Wrote {} missing ids to {}",
+            result.missing.len(),
+            retry_file.display()
+        );
+    }
+
+    Ok(if !result.missing.is_empty() {
+        ExitCode::from(EXIT_MISSING)
+    } else if !result.extra.is_empty() {
+        ExitCode::from(EXIT_EXTRA)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn ids_check<'a>(post_ids: &'a [String], archived: &'a HashSet<String>) -> IdsCheck<'a> {
+    let source = post_ids.iter().map(String::as_str).collect::<HashSet<_>>();
+    let mut extra = archived
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !source.contains(id))
+        .collect::<Vec<_>>();
+    extra.sort_unstable();
+
+    let mut seen = HashSet::new();
+    let missing = post_ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !archived.contains(*id) && seen.insert(*id))
+        .collect();
+
+    IdsCheck { missing, extra }
+}
+
+/// Ids of downloaded posts, taken from file stems in the archive.
+fn archived_ids(archive_dir: &Path) -> Result<HashSet<String>> {
+    let mut ids = HashSet::new();
+    for entry in fs::read_dir(archive_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(id) = entry.path().file_stem() {
+            ids.insert(id.to_string_lossy().into_owned());
+        }
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_check() {
+        let post_ids = ["c", "a", "d", "b", "a"].map(String::from);
+        let archived = ["a", "x", "b", "w"].map(String::from).into();
+
+        assert_eq!(
+            ids_check(&post_ids, &archived),
+            IdsCheck {
+                missing: vec!["c", "d"],
+                extra: vec!["w", "x"],
+            }
+        );
+    }
+}
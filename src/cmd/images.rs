@@ -0,0 +1,491 @@
+use color_eyre::{
+    Result,
+    eyre::{Context, bail, ensure, eyre},
+};
+use indicatif::{ParallelProgressIterator, ProgressStyle};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use url::Url;
+
+use crate::{
+    cmd::Common,
+    http,
+    model::{CommentThread, PostWithMeta},
+};
+
+/// Placeholder of posts without a thumbnail, not worth downloading.
+const NO_THUMBNAIL: &str = "https://i.ytimg.com/img/no_thumbnail.jpg";
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[arg(long, value_name = "DIR", default_value = "archive_imgs")]
+    /// Directory to download images into, along with its `manifest.json`.
+    images_dir: PathBuf,
+    #[arg(long, value_name = "URL")]
+    /// Download from this base url instead of the image's origin (ie. a mirror), keeping the path.
+    base_url: Option<Url>,
+    #[arg(long, short, default_value_t = 4)]
+    /// Number of concurrent downloads.
+    jobs: usize,
+    #[arg(long, default_value_t = 3)]
+    /// Attempts per url before falling back to a lower resolution.
+    retries: u32,
+}
+
+/// Downloaded image, keyed by its url in posts.json.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestEntry {
+    /// File name in the images dir.
+    pub file: String,
+    /// Url the image was actually downloaded from, may be a different resolution.
+    pub source: Option<String>,
+    pub blake3: String,
+    pub size: u64,
+}
+
+pub(crate) type Manifest = BTreeMap<String, ManifestEntry>;
+
+pub fn run(common: &Common, args: Args) -> Result<()> {
+    let Args {
+        images_dir,
+        base_url,
+        jobs,
+        retries,
+    } = args;
+    ensure!(jobs > 0, "--jobs should be at least 1");
+    fs::create_dir_all(&images_dir)
+        .wrap_err_with(|| format!("fail to create {}", images_dir.display()))?;
+
+    let posts = common.read_posts()?;
+    let files = file_names(image_urls(&posts));
+
+    let manifest_file = images_dir.join("manifest.json");
+    let mut manifest = read_manifest(&manifest_file)?;
+    manifest.retain(|url, _| files.contains_key(url));
+
+    let mut pending = Vec::new();
+    for (url, file) in &files {
+        let path = images_dir.join(file);
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+        if size == 0 {
+            pending.push((url, file));
+            continue;
+        }
+        // downloaded before the manifest was kept
+        if manifest.get(url).is_none_or(|entry| entry.size != size) {
+            manifest.insert(url.clone(), entry(&path, file, None)?);
+        }
+    }
+    println!(
+        "{} images, {} already downloaded",
+        files.len(),
+        files.len() - pending.len()
+    );
+
+    let agent = http::agent();
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    let results = pool.install(|| {
+        pending
+            .par_iter()
+            .progress()
+            .with_style(
+                ProgressStyle::with_template("{wide_bar} {pos}/{len} {per_sec} {eta}").unwrap(),
+            )
+            .map(|&(url, file)| {
+                let result = download(
+                    &agent,
+                    url,
+                    &images_dir.join(file),
+                    base_url.as_ref(),
+                    retries,
+                )
+                .and_then(|source| entry(&images_dir.join(file), file, Some(source)));
+                (url, result)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut failed = 0;
+    for (url, result) in results {
+        match result {
+            Ok(entry) => {
+                manifest.insert(url.clone(), entry);
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("Failed to download {url}: {err:#}");
+            }
+        }
+    }
+
+    let mut writer = BufWriter::new(File::create(&manifest_file)?);
+    serde_json::to_writer_pretty(&mut writer, &manifest)?;
+    writer.flush()?;
+    println!(
+        "Downloaded {} images, manifest written to `{}`",
+        pending.len() - failed,
+        manifest_file.display()
+    );
+
+    ensure!(
+        failed == 0,
+        "{failed}/{} images failed to download",
+        pending.len()
+    );
+    Ok(())
+}
+
+/// Post attachment images and comment sponsor badges, without query.
+fn image_urls(posts: &[PostWithMeta]) -> BTreeSet<String> {
+    fn sponsor_badges<'a>(threads: &'a [CommentThread], urls: &mut Vec<&'a str>) {
+        for thread in threads {
+            urls.extend(thread.comment.sponsor_badge.as_deref());
+            sponsor_badges(&thread.replies, urls);
+        }
+    }
+
+    let mut urls = Vec::new();
+    for post in posts {
+        if let Some(attachment) = &post.post.main.content_attachment {
+            urls.extend(attachment.images.iter().map(String::as_str));
+        }
+        if let Some(comments) = &post.post.comments {
+            sponsor_badges(comments, &mut urls);
+        }
+    }
+
+    urls.into_iter()
+        .map(|url| url.split('?').next().unwrap_or(url).to_owned())
+        .filter(|url| url != NO_THUMBNAIL)
+        .collect()
+}
+
+/// Assign file names to urls, the url path percent-encoded (same as `scripts/download_imgs.py`).
+/// Colliding names get a hash suffix.
+fn file_names(urls: BTreeSet<String>) -> BTreeMap<String, String> {
+    let mut seen = HashMap::<String, usize>::new();
+    let names = urls
+        .iter()
+        .map(|url| {
+            let path = url
+                .split_once("://")
+                .map_or(url.as_str(), |(_, rest)| rest)
+                .split_once('/')
+                .map_or("", |(_, path)| path);
+            let name = percent_encode(path);
+            *seen.entry(name.clone()).or_default() += 1;
+            name
+        })
+        .collect::<Vec<_>>();
+
+    urls.into_iter()
+        .zip(names)
+        .map(|(url, name)| {
+            let name = if seen[&name] > 1 {
+                let hash = blake3::hash(url.as_bytes()).to_hex();
+                format!("{name}-{}", &hash[..8])
+            } else {
+                name
+            };
+            (url, name)
+        })
+        .collect()
+}
+
+pub(crate) fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// Urls to try for an image, highest resolution first.
+fn candidates(url: &str) -> Vec<String> {
+    let mut candidates = Vec::new();
+    let (dir, name) = url.rsplit_once('/').unwrap_or(("", url));
+
+    if url.contains("ggpht.com/") || url.contains("googleusercontent.com/") {
+        // resized by options after `=` (ie. `=s1024-c-k-nd`), `=s0` is the original size
+        if let Some((id, _)) = name.split_once('=') {
+            candidates.push(format!("{dir}/{id}=s0"));
+            candidates.push(url.to_owned());
+            candidates.push(format!("{dir}/{id}"));
+        }
+    } else if url.contains("i.ytimg.com/vi/") && name != "maxresdefault.jpg" {
+        candidates.push(format!("{dir}/maxresdefault.jpg"));
+    }
+    candidates.push(url.to_owned());
+
+    let mut seen = BTreeSet::new();
+    candidates.retain(|c| seen.insert(c.clone()));
+    candidates
+}
+
+fn with_base_url(url: &str, base_url: &Url) -> Result<String> {
+    let url = Url::parse(url)?;
+    let mut rebased = base_url.clone();
+    let base_path = base_url.path().trim_end_matches('/');
+    rebased.set_path(&format!("{base_path}{}", url.path()));
+    rebased.set_query(url.query());
+    Ok(rebased.into())
+}
+
+/// Download `url` into `path`, falling back to lower resolutions.
+/// Returns the url actually downloaded from.
+fn download(
+    agent: &ureq::Agent,
+    url: &str,
+    path: &Path,
+    base_url: Option<&Url>,
+    retries: u32,
+) -> Result<String> {
+    let mut errors = Vec::new();
+    for candidate in candidates(url) {
+        let source = match base_url {
+            Some(base_url) => with_base_url(&candidate, base_url)?,
+            None => candidate,
+        };
+        // partial download of each source is kept separately so it is only resumed from the same source
+        let part = path.with_file_name(format!(
+            "{}.{}.part",
+            path.file_name().unwrap().to_string_lossy(),
+            &blake3::hash(source.as_bytes()).to_hex()[..8]
+        ));
+
+        let attempts = retries.max(1);
+        for attempt in 0..attempts {
+            match fetch(agent, &source, &part) {
+                Ok(()) => {
+                    fs::rename(&part, path)?;
+                    return Ok(source);
+                }
+                Err(Fetch::Unavailable(err)) => {
+                    errors.push(format!("{source}: {err}"));
+                    break;
+                }
+                Err(Fetch::Retry(err)) => {
+                    errors.push(format!("{source} (attempt {}): {err}", attempt + 1));
+                    if attempt + 1 < attempts {
+                        thread::sleep(retry_delay(attempt));
+                    }
+                }
+            }
+        }
+    }
+    bail!("all urls failed:\n{}", errors.join("\n"))
+}
+
+/// Backoff before retrying after `attempt` failed, doubling up to 32 seconds.
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_millis(500 * 2u64.pow(attempt.min(6)))
+}
+
+enum Fetch {
+    /// Not worth retrying, ie. 404.
+    Unavailable(String),
+    Retry(String),
+}
+
+/// Download `url` into `part`, resuming from its current length.
+fn fetch(agent: &ureq::Agent, url: &str, part: &Path) -> Result<(), Fetch> {
+    let retry = |err: &dyn std::fmt::Display| Fetch::Retry(err.to_string());
+
+    let offset = fs::metadata(part).map(|m| m.len()).unwrap_or_default();
+    let mut request = agent.get(url);
+    if offset > 0 {
+        request = request.header("Range", format!("bytes={offset}-"));
+    }
+    let mut response = request.call().map_err(|err| retry(&err))?;
+
+    let status = response.status().as_u16();
+    let mut file = match status {
+        206 => OpenOptions::new()
+            .append(true)
+            .open(part)
+            .map_err(|err| retry(&err))?,
+        200 => File::create(part).map_err(|err| retry(&err))?,
+        // partial file is already complete
+        416 if offset > 0 => {
+            fs::remove_file(part).map_err(|err| retry(&err))?;
+            return Err(Fetch::Retry(format!("HTTP {status}, restarting")));
+        }
+        408 | 429 | 500.. => return Err(Fetch::Retry(format!("HTTP {status}"))),
+        _ => return Err(Fetch::Unavailable(format!("HTTP {status}"))),
+    };
+
+    io::copy(&mut response.body_mut().as_reader(), &mut file).map_err(|err| retry(&err))?;
+    file.flush().map_err(|err| retry(&err))?;
+    Ok(())
+}
+
+fn entry(path: &Path, file: &str, source: Option<String>) -> Result<ManifestEntry> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(ManifestEntry {
+        file: file.to_owned(),
+        source,
+        blake3: hasher.finalize().to_hex().to_string(),
+        size: fs::metadata(path)?.len(),
+    })
+}
+
+pub(crate) fn read_manifest(path: &Path) -> Result<Manifest> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file))
+            .wrap_err_with(|| format!("fail to parse {}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Manifest::new()),
+        Err(err) => Err(eyre!(err).wrap_err(format!("fail to open {}", path.display()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{
+        agent,
+        test_server::{Routes, serve},
+    };
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(
+            candidates("https://yt3.ggpht.com/abc=s1024-c-fcrop64=1,00000000ffffffff-nd-v1"),
+            [
+                "https://yt3.ggpht.com/abc=s0",
+                "https://yt3.ggpht.com/abc=s1024-c-fcrop64=1,00000000ffffffff-nd-v1",
+                "https://yt3.ggpht.com/abc",
+            ]
+        );
+        assert_eq!(
+            candidates("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"),
+            [
+                "https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg",
+                "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg",
+            ]
+        );
+        assert_eq!(
+            candidates("https://example.com/a.png"),
+            ["https://example.com/a.png"]
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(0), Duration::from_millis(500));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(6), Duration::from_secs(32));
+        assert_eq!(retry_delay(u32::MAX), Duration::from_secs(32));
+    }
+
+    #[test]
+    fn test_file_names() {
+        let names = file_names(BTreeSet::from([
+            "https://yt3.ggpht.com/abc=s1024-nd".to_owned(),
+            "https://yt3.googleusercontent.com/abc=s1024-nd".to_owned(),
+            "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg".to_owned(),
+        ]));
+        assert_eq!(
+            names["https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"],
+            "vi%2FdQw4w9WgXcQ%2Fhqdefault.jpg"
+        );
+        let a = &names["https://yt3.ggpht.com/abc=s1024-nd"];
+        let b = &names["https://yt3.googleusercontent.com/abc=s1024-nd"];
+        assert!(a.starts_with("abc%3Ds1024-nd-"), "{a}");
+        assert!(b.starts_with("abc%3Ds1024-nd-"), "{b}");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_download_fallback_and_retry() {
+        let (base_url, log) = serve(Routes::from([
+            ("/abc=s0", vec![(404, b"".as_slice())]),
+            (
+                "/abc=s1024-nd",
+                vec![(500, b"".as_slice()), (200, b"image")],
+            ),
+        ]));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc");
+
+        let source = download(
+            &agent(),
+            "https://yt3.ggpht.com/abc=s1024-nd",
+            &path,
+            Some(&base_url),
+            3,
+        )
+        .unwrap();
+
+        assert_eq!(source, format!("{base_url}abc=s1024-nd"));
+        assert_eq!(fs::read(&path).unwrap(), b"image");
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "/abc=s0 404 0",
+                "/abc=s1024-nd 500 0",
+                "/abc=s1024-nd 200 0"
+            ]
+        );
+
+        let entry = entry(&path, "abc", Some(source)).unwrap();
+        assert_eq!(entry.size, 5);
+        assert_eq!(entry.blake3, blake3::hash(b"image").to_hex().as_str());
+    }
+
+    #[test]
+    fn test_download_resume() {
+        let (base_url, log) = serve(Routes::from([(
+            "/vi/x/maxresdefault.jpg",
+            vec![(200, b"0123456789".as_slice())],
+        )]));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x");
+        let source =
+            with_base_url("https://i.ytimg.com/vi/x/maxresdefault.jpg", &base_url).unwrap();
+        let part = dir.path().join(format!(
+            "x.{}.part",
+            &blake3::hash(source.as_bytes()).to_hex()[..8]
+        ));
+        fs::write(&part, b"0123").unwrap();
+
+        download(
+            &agent(),
+            "https://i.ytimg.com/vi/x/hqdefault.jpg",
+            &path,
+            Some(&base_url),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"0123456789");
+        assert!(!part.exists());
+        assert_eq!(*log.lock().unwrap(), ["/vi/x/maxresdefault.jpg 206 4"]);
+    }
+
+    #[test]
+    fn test_download_unavailable() {
+        let (base_url, _) = serve(Routes::new());
+        let dir = tempfile::tempdir().unwrap();
+        let err = download(
+            &agent(),
+            "https://example.com/a.png",
+            &dir.path().join("a.png"),
+            Some(&base_url),
+            3,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("/a.png: HTTP 404"), "{err}");
+    }
+}
@@ -0,0 +1,75 @@
+use color_eyre::{Result, eyre::Context};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    cmd::{Common, is_jsonl},
+    jsonl,
+    migrate::migrate,
+    model::SCHEMA_VERSION,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[arg(long, value_name = "FILE")]
+    /// File to write the upgraded posts into, in the format of the input, defaults to overwriting the input (keeping a `posts.v<version>.bak` backup).
+    output: Option<PathBuf>,
+}
+
+pub fn run(common: &Common, args: Args) -> Result<()> {
+    // `--posts` or the posts in output dir
+    let input = common.input_file()?;
+    let jsonl = is_jsonl(&input);
+    let reader = BufReader::new(
+        File::open(&input).wrap_err_with(|| format!("fail to open {}", input.display()))?,
+    );
+    let value = if jsonl {
+        jsonl::read_value(reader)
+    } else {
+        serde_json::from_reader(reader).map_err(Into::into)
+    }
+    .wrap_err_with(|| format!("fail to parse {}", input.display()))?;
+    let (from, archive) = migrate(value)?;
+    if from == SCHEMA_VERSION && args.output.is_none() {
+        println!(
+            "{} is already at schema version {SCHEMA_VERSION}",
+            input.display()
+        );
+        return Ok(());
+    }
+
+    let output = match args.output {
+        Some(output) => output,
+        None => {
+            let backup = if jsonl {
+                input.with_extension(format!("v{from}.jsonl.bak"))
+            } else {
+                input.with_extension(format!("v{from}.bak"))
+            };
+            fs::copy(&input, &backup)
+                .wrap_err_with(|| format!("fail to back up to {}", backup.display()))?;
+            println!("Backed up {} to {}", input.display(), backup.display());
+            input.clone()
+        }
+    };
+    let part = output.with_extension("part");
+    let mut writer = BufWriter::new(File::create(&part)?);
+    if jsonl {
+        jsonl::write(&mut writer, &archive)?;
+    } else {
+        serde_json::to_writer(&mut writer, &archive)?;
+        writer.flush()?;
+    }
+    drop(writer);
+    fs::rename(&part, &output)?;
+
+    println!(
+        "Migrated {} posts from schema version {from} to {SCHEMA_VERSION} into {}",
+        archive.posts.len(),
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,431 @@
+use color_eyre::{
+    Result, Section,
+    eyre::{Context, ContextCompat, ensure, eyre},
+};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    fs::{self, DirEntry, File},
+    io::{self, BufWriter, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Instant, SystemTime},
+};
+
+use crate::{
+    cache::ParseCache,
+    cmd::Common,
+    error::ParseError,
+    jsonl::{Header, OrderedWriter},
+    link::YOUTUBE_ORIGIN,
+    locale::Locale,
+    model::{Archive, Meta, PostWithMeta},
+    parse::{PARSER_VERSION, ParseContext, parse},
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[arg(long, value_enum)]
+    /// UI language the posts were saved in, use for parsing numbers.
+    /// Detected from each page's `<html lang>` if not specified, falling back to Thai.
+    locale: Option<Locale>,
+    #[arg(long, value_name = "TIMESTAMP")]
+    /// Time the posts were captured (ie. `2024-01-17T12:00:00Z`), use for resolving relative publish times.
+    /// Defaults to each file's modification time.
+    captured_at: Option<jiff::Timestamp>,
+    #[arg(long)]
+    /// Only parse new or changed posts, reusing the rest from the parse cache (`<output-dir>/cache`).
+    incremental: bool,
+    #[arg(long)]
+    /// Keep going when a post fails to parse, writing the parsed posts and an errors report (`<output-dir>/errors.json`).
+    keep_going: bool,
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    /// Output format.
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Format {
+    /// A single JSON document (`posts.json`), written once every post is parsed.
+    Json,
+    /// JSON Lines (`posts.jsonl`), a header line then one post per line, written while parsing.
+    Jsonl,
+}
+
+pub fn run(common: &Common, args: Args) -> Result<()> {
+    let Args {
+        locale,
+        captured_at,
+        incremental,
+        keep_going,
+        format,
+    } = args;
+    let archive_dir = &common.archive_dir;
+    let output_file = match format {
+        Format::Json => common.posts_file(),
+        Format::Jsonl => common.posts_jsonl_file(),
+    };
+    let errors_file = common.output_dir.join("errors.json");
+    let coverage_file = common.output_dir.join("emote_coverage.json");
+    let mismatches_file = common.output_dir.join("comment_id_mismatches.json");
+    println!("Processing posts in `{}`", archive_dir.display());
+
+    let t0 = Instant::now();
+    let dirs = fs::read_dir(archive_dir)?.collect::<io::Result<Vec<_>>>()?;
+    if dirs.is_empty() {
+        println!("No posts to process");
+        return Ok(());
+    }
+
+    let emote_resolver = &common.emote_resolver()?;
+    for conflict in emote_resolver.conflicts() {
+        eprintln!("Warning: {conflict}");
+    }
+    let post_ids = common.read_post_ids_lenient();
+    let post_ranks = post_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let post_order = |id: &str| post_ranks.get(id).copied();
+
+    // parsed in this order, so posts reach the output as soon as they are parsed
+    let dirs = dirs
+        .into_iter()
+        .map(|dir| {
+            let id = dir
+                .path()
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string());
+            (id, dir)
+        })
+        .sorted_by_cached_key(|(id, _)| (id.as_deref().and_then(post_order), id.clone()))
+        .map(|(_, dir)| dir)
+        .collect::<Vec<_>>();
+
+    // posts are streamed into a part file, renamed once done
+    let part_file = output_file.with_extension("part");
+    let mut stream = (format == Format::Jsonl)
+        .then(|| -> Result<_> {
+            let order = dirs
+                .iter()
+                .filter_map(|dir| Some(dir.path().file_stem()?.to_string_lossy().to_string()))
+                .collect();
+            let writer = BufWriter::new(File::create(&part_file)?);
+            OrderedWriter::new(writer, &Header::new(Some(PARSER_VERSION)), order)
+        })
+        .transpose()?;
+
+    let cache = incremental
+        .then(|| {
+            ParseCache::open(
+                &common.output_dir.join("cache"),
+                &[
+                    format!("{locale:?}").as_bytes(),
+                    &emote_resolver.fingerprint(),
+                ],
+            )
+        })
+        .transpose()?;
+
+    let total_bytes = AtomicU64::new(0);
+    let cached = AtomicU64::new(0);
+    let parsed = AtomicUsize::new(0);
+    let mismatches = Mutex::new(Vec::new());
+    let progress = ProgressBar::new(dirs.len() as u64).with_style(ProgressStyle::with_template(
+        "{wide_bar} {pos}/{len} {per_sec} {eta}",
+    )?);
+    let process = |dir: &DirEntry| {
+        let t0 = Instant::now();
+        let content = fs::read_to_string(dir.path())?;
+        let name = dir
+            .path()
+            .file_stem()
+            .context("no file name")?
+            .to_string_lossy()
+            .to_string();
+        total_bytes.fetch_add(content.len() as u64, Ordering::Relaxed);
+
+        let metadata = dir.metadata().ok();
+        let created_at = metadata.as_ref().and_then(|m| m.created().ok());
+        let modified_at = metadata.as_ref().and_then(|m| m.modified().ok());
+        let processed_at = SystemTime::now();
+
+        let source_modified_at = modified_at.map(|t| t.try_into().unwrap());
+        let anchor = captured_at.or(source_modified_at);
+
+        let key = cache.as_ref().map(|cache| {
+            cache.key(
+                &content,
+                &[name.as_bytes(), format!("{anchor:?}").as_bytes()],
+            )
+        });
+        if let Some((cache, key)) = cache.as_ref().zip(key.as_ref())
+            && let Some(post) = cache.get(key)
+        {
+            cached.fetch_add(1, Ordering::Relaxed);
+            emote_resolver.record_post(&post.post);
+            mismatches
+                .lock()
+                .unwrap()
+                .extend(post.post.comment_id_mismatches());
+            return Ok(Ok(post));
+        }
+
+        let cx = ParseContext {
+            locale,
+            captured_at: anchor,
+            ..ParseContext::new(emote_resolver)
+        };
+        let post = match parse(&content, name, &cx) {
+            Ok(post) => post,
+            Err(err) => return Ok(Err(err)),
+        };
+        mismatches
+            .lock()
+            .unwrap()
+            .extend(post.comment_id_mismatches());
+        let elapsed = t0.elapsed();
+
+        let meta = Meta {
+            source_created_at: created_at.map(|t| t.try_into().unwrap()),
+            source_modified_at,
+            processed_at: processed_at.try_into().unwrap(),
+            process_time: elapsed.try_into().unwrap(),
+        };
+        let post = PostWithMeta { post, meta };
+
+        if let Some((cache, key)) = cache.as_ref().zip(key.as_ref()) {
+            cache.insert(key, &post)?;
+        }
+        Ok::<_, color_eyre::eyre::Error>(Ok(post))
+    };
+    let mut results = Vec::new();
+    par_map_ordered(
+        &dirs,
+        PARSE_BATCH_SIZE,
+        |dir| {
+            let result = process(dir);
+            progress.inc(1);
+            result
+        },
+        |result| {
+            let result = result?;
+            if result.is_ok() {
+                parsed.fetch_add(1, Ordering::Relaxed);
+            }
+            let Some(stream) = &mut stream else {
+                results.push(result);
+                return Ok(());
+            };
+            match result {
+                Ok(post) => stream.push(post)?,
+                Err(err) => {
+                    if let Some(id) = &err.post_id {
+                        stream.skip(id)?;
+                    }
+                    results.push(Err(err));
+                }
+            }
+            Ok(())
+        },
+    )?;
+    progress.finish();
+    // in posts order already
+    let (posts, mut errors): (Vec<PostWithMeta>, Vec<ParseError>) =
+        results.into_iter().partition_result();
+    let parsed = parsed.into_inner();
+
+    if let Some(cache) = &cache {
+        let pruned = cache.prune()?;
+        println!(
+            "Reused {} cached posts, pruned {} stale cache entries",
+            cached.into_inner(),
+            pruned
+        );
+    }
+
+    if !keep_going && !errors.is_empty() {
+        if stream.is_some() {
+            fs::remove_file(&part_file)?;
+        }
+        let n = errors.len();
+        return Err(eyre!(errors.swap_remove(0)))
+            .wrap_err(format!("{n} posts failed to parse"))
+            .suggestion("use --keep-going to write the parsed posts and an errors report");
+    }
+
+    let elapsed = t0.elapsed();
+    let total_bytes = total_bytes.into_inner();
+    println!(
+        "Processing {} posts done in : {:.2?} ({:.2} post/s), Total bytes: {} ({}/s)",
+        parsed,
+        elapsed,
+        parsed as f64 / elapsed.as_secs_f64(),
+        HumanBytes(total_bytes),
+        HumanBytes((1_000_000_000 * total_bytes as u128 / elapsed.as_nanos()) as u64)
+    );
+
+    // Write JSON
+    let t0 = Instant::now();
+    match stream {
+        Some(stream) => {
+            stream.finish()?;
+            fs::rename(&part_file, &output_file)?;
+        }
+        None => {
+            let mut posts_writer = BufWriter::new(File::create(&output_file)?);
+            serde_json::to_writer(
+                &mut posts_writer,
+                &Archive::new(posts, Some(PARSER_VERSION)),
+            )?;
+            posts_writer.flush()?;
+        }
+    }
+    println!(
+        "Writing {} done in : {:.2?}",
+        output_file.display(),
+        t0.elapsed()
+    );
+    let other_file = match format {
+        Format::Json => common.posts_jsonl_file(),
+        Format::Jsonl => common.posts_file(),
+    };
+    if other_file.exists() {
+        println!(
+            "{} also exists, other commands need `--posts` to choose between them unless it is removed",
+            other_file.display()
+        );
+    }
+
+    // Emote coverage report
+    let coverage = emote_resolver.take_coverage();
+    let mut coverage_writer = BufWriter::new(File::create(&coverage_file)?);
+    serde_json::to_writer_pretty(&mut coverage_writer, &coverage)?;
+    coverage_writer.flush()?;
+    println!(
+        "Emotes: {}",
+        coverage
+            .counts
+            .iter()
+            .map(|(resolution, count)| format!("{count} {resolution:?}"))
+            .join(", ")
+    );
+    for (id, sources) in &coverage.unresolved {
+        println!("Unresolved emote {id} used in:");
+        for source in sources {
+            println!("\t- {YOUTUBE_ORIGIN}{source}");
+        }
+    }
+    if !coverage.unresolved.is_empty() {
+        println!(
+            "{} unresolved emotes, add them to the emote mapping (see `{}`)",
+            coverage.unresolved.len(),
+            coverage_file.display()
+        );
+    }
+
+    // Comment id mismatch report
+    let mut mismatches = mismatches.into_inner().unwrap();
+    mismatches.sort_by_key(|m| post_order(&m.post_id));
+    let mut mismatches_writer = BufWriter::new(File::create(&mismatches_file)?);
+    serde_json::to_writer_pretty(&mut mismatches_writer, &mismatches)?;
+    mismatches_writer.flush()?;
+    for m in &mismatches {
+        println!(
+            "Comment {YOUTUBE_ORIGIN}{} has {} {:?}, expected {:?}",
+            m.url, m.field, m.found, m.expected
+        );
+    }
+    if !mismatches.is_empty() {
+        println!(
+            "{} comments with ids not matching where they are nested (see `{}`)",
+            mismatches.len(),
+            mismatches_file.display()
+        );
+    }
+
+    if keep_going {
+        let mut errors_writer = BufWriter::new(File::create(&errors_file)?);
+        serde_json::to_writer_pretty(&mut errors_writer, &errors)?;
+        errors_writer.flush()?;
+
+        for err in &errors {
+            eprintln!("{err}");
+        }
+        ensure!(
+            errors.is_empty(),
+            "{}/{} posts failed to parse, see `{}`",
+            errors.len(),
+            errors.len() + parsed,
+            errors_file.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Posts parsed at once, the most the JSON Lines writer may hold before writing them out.
+const PARSE_BATCH_SIZE: usize = 256;
+
+/// Map `items` in parallel, feeding the results to `sink` in the order of `items`.
+/// Done in batches of `batch_size`, so no more than one batch of results is held at a time.
+fn par_map_ordered<T: Sync, R: Send>(
+    items: &[T],
+    batch_size: usize,
+    map: impl Fn(&T) -> R + Sync,
+    mut sink: impl FnMut(R) -> Result<()>,
+) -> Result<()> {
+    for batch in items.chunks(batch_size) {
+        let results = batch.par_iter().map(&map).collect::<Vec<_>>();
+        for result in results {
+            sink(result)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tests::post;
+
+    #[test]
+    fn test_par_map_ordered() {
+        let ids = (0..1000).map(|i| format!("{i:04}")).collect::<Vec<_>>();
+        let mut writer = OrderedWriter::new(Vec::new(), &Header::new(None), ids.clone()).unwrap();
+        let mut max_buffered = 0;
+        par_map_ordered(
+            &ids,
+            16,
+            |id| post(id),
+            |post| {
+                writer.push(post)?;
+                max_buffered = max_buffered.max(writer.buffered());
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(max_buffered, 0);
+        assert_eq!(writer.written(), ids.len());
+
+        let mut seen = Vec::new();
+        let err = par_map_ordered(
+            &ids,
+            16,
+            |id| id.clone(),
+            |id| {
+                ensure!(id != "0100", "stop");
+                seen.push(id);
+                Ok(())
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "stop");
+        assert_eq!(seen, ids[..100]);
+    }
+}
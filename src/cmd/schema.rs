@@ -0,0 +1,8 @@
+use color_eyre::Result;
+
+use crate::model::Archive;
+
+pub fn run() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&Archive::json_schema())?);
+    Ok(())
+}
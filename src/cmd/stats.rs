@@ -0,0 +1,110 @@
+use color_eyre::Result;
+
+use crate::{
+    cmd::Common,
+    model::{Comment, CommentThread},
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[arg(long)]
+    /// Print as JSON instead of text.
+    json: bool,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct Stats {
+    posts: usize,
+    sponsor_only_posts: usize,
+    comments_disabled_posts: usize,
+    polls: usize,
+    images: usize,
+    videos: usize,
+    likes: u64,
+    comment_threads: usize,
+    /// Visible comments, including replies.
+    comments: usize,
+    /// Comment count reported by YouTube, may include hidden comments.
+    reported_comments: u64,
+    /// Comments hearted by the channel owner.
+    hearted_comments: usize,
+    /// Comments written by the channel owner.
+    channel_owner_comments: usize,
+}
+
+pub fn run(common: &Common, args: Args) -> Result<()> {
+    let posts = common.read_posts()?;
+
+    let mut stats = Stats::default();
+    for post in &posts {
+        let post = &post.post;
+        stats.posts += 1;
+        stats.sponsor_only_posts += post.main.sponsor_only.is_some() as usize;
+        stats.comments_disabled_posts += post.comments.is_none() as usize;
+        stats.polls += post.main.poll_attachment.is_some() as usize;
+        if let Some(attachment) = &post.main.content_attachment {
+            stats.images += attachment.images.len();
+            stats.videos += attachment
+                .links
+                .iter()
+                .filter(|link| link.target.is_video())
+                .count();
+        }
+        stats.likes += u64::from(post.main.like);
+        stats.reported_comments += u64::from(post.total_comment.unwrap_or(0));
+        if let Some(comments) = &post.comments {
+            stats.comment_threads += comments.len();
+            stats.comments += comments
+                .iter()
+                .map(|thread| count_comments(thread, &|_| true))
+                .sum::<usize>();
+            stats.hearted_comments += comments
+                .iter()
+                .map(|thread| count_comments(thread, &|c| c.hearted))
+                .sum::<usize>();
+            stats.channel_owner_comments += comments
+                .iter()
+                .map(|thread| count_comments(thread, &|c| c.channel_owner))
+                .sum::<usize>();
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        let Stats {
+            posts,
+            sponsor_only_posts,
+            comments_disabled_posts,
+            polls,
+            images,
+            videos,
+            likes,
+            comment_threads,
+            comments,
+            reported_comments,
+            hearted_comments,
+            channel_owner_comments,
+        } = stats;
+        println!(
+            "Posts: {posts} ({sponsor_only_posts} members-only, {comments_disabled_posts} with comments disabled)"
+        );
+        println!("Attachments: {polls} polls, {images} images, {videos} videos");
+        println!("Likes: {likes}");
+        println!(
+            "Comments: {comments} in {comment_threads} threads ({reported_comments} reported), \
+             {hearted_comments} hearted, {channel_owner_comments} by the channel owner"
+        );
+    }
+    Ok(())
+}
+
+/// Comments in the thread, including replies, matching `filter`.
+fn count_comments(thread: &CommentThread, filter: &impl Fn(&Comment) -> bool) -> usize {
+    usize::from(filter(&thread.comment))
+        + thread
+            .replies
+            .iter()
+            .map(|reply| count_comments(reply, filter))
+            .sum::<usize>()
+}
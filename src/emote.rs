@@ -1,81 +1,430 @@
-use std::{collections::HashMap, fs::File, io, path::Path};
+use color_eyre::{
+    Result,
+    eyre::{Context, bail, eyre},
+};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use url::Url;
+
+use crate::{
+    model::{CommentThread, Post},
+    rich_text::{RichText, Span},
+};
+
+/// Origin of custom emote images.
+pub const EMOTE_BASE_URL: &str = "https://yt3.ggpht.com/";
+
+/// Resolved emoji image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Emoji {
+    Unicode(String),
+    /// Custom channel emote, by name.
+    Custom(String),
+}
 
 #[derive(Debug)]
 pub struct EmoteResolver {
     mapping: HashMap<String, String>,
+    /// Mapping file of each id, as index into `files`.
+    sources: HashMap<String, usize>,
+    files: Vec<PathBuf>,
+    /// Ids named differently by a later mapping file, which wins.
+    conflicts: Vec<String>,
+    coverage: Mutex<EmoteCoverage>,
 }
 
 impl EmoteResolver {
-    pub fn with_emote_dir(emote_dir: &Path) -> Self {
-        Self {
-            mapping: load_emoji_mapping(emote_dir),
+    /// Load `emote_mapping_default.json` and `emote_mapping.json` of `data_dir` if they exist, then every `extra` mapping file.
+    /// Every file is a `{"<id>": "<name>"}` object, an id named differently by a later file takes the later name
+    /// and is reported in [`EmoteResolver::conflicts`].
+    pub fn load(data_dir: &Path, extra: &[PathBuf]) -> Result<Self> {
+        let files = [
+            (data_dir.join("emote_mapping_default.json"), false),
+            (data_dir.join("emote_mapping.json"), false),
+        ]
+        .into_iter()
+        .chain(extra.iter().map(|file| (file.clone(), true)));
+
+        let mut resolver = Self::with_mapping(HashMap::new());
+        let mut problems = Vec::new();
+        for (file, required) in files {
+            let mapping = match File::open(&file) {
+                Ok(reader) => serde_json::from_reader::<_, BTreeMap<String, String>>(
+                    BufReader::new(reader),
+                )
+                .wrap_err_with(|| format!("fail to parse emote mapping {}", file.display()))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound && !required => continue,
+                Err(err) => {
+                    return Err(err).wrap_err_with(|| {
+                        format!("fail to read emote mapping {}", file.display())
+                    });
+                }
+            };
+
+            let index = resolver.files.len();
+            for (id, name) in mapping {
+                if !is_emote_id(&id) {
+                    problems.push(format!("{}: `{id}` is not an emote id", file.display()));
+                    continue;
+                }
+                if name.trim().is_empty() {
+                    problems.push(format!("{}: `{id}` has an empty name", file.display()));
+                    continue;
+                }
+                match resolver.mapping.get(&id) {
+                    Some(existing) if *existing == name => {}
+                    existing => {
+                        if let Some(existing) = existing {
+                            let conflict = format!(
+                                "`{id}` is `{existing}` in {} but `{name}` in {}, using `{name}`",
+                                resolver.files[resolver.sources[&id]].display(),
+                                file.display()
+                            );
+                            resolver.conflicts.push(conflict);
+                        }
+                        resolver.sources.insert(id.clone(), index);
+                        resolver.mapping.insert(id, name);
+                    }
+                }
+            }
+            resolver.files.push(file);
+        }
+
+        if !problems.is_empty() {
+            bail!(
+                "{} problems in emote mappings:\n{}",
+                problems.len(),
+                problems.iter().map(|p| format!("  - {p}")).join("\n")
+            );
         }
+        Ok(resolver)
     }
 
     pub fn with_mapping(mapping: HashMap<String, String>) -> Self {
-        Self { mapping }
+        Self {
+            mapping,
+            sources: HashMap::new(),
+            files: Vec::new(),
+            conflicts: Vec::new(),
+            coverage: Mutex::default(),
+        }
+    }
+
+    /// Custom emote id to name mapping.
+    pub fn mapping(&self) -> &HashMap<String, String> {
+        &self.mapping
     }
 
-    pub fn resolve_emoji(&self, src: &str, alt: Option<&str>) -> Option<String> {
+    /// Mapping file an id was loaded from.
+    pub fn source(&self, id: &str) -> Option<&Path> {
+        self.sources.get(id).map(|&i| self.files[i].as_path())
+    }
+
+    /// Ids named differently by mapping files, with both files and names.
+    pub fn conflicts(&self) -> &[String] {
+        &self.conflicts
+    }
+
+    /// Stable serialization of the mapping, use for detecting mapping changes.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        let sorted = self.mapping.iter().collect::<BTreeMap<_, _>>();
+        serde_json::to_vec(&sorted).expect("mapping of strings should be serializable")
+    }
+
+    /// Resolve an emoji image found in `source` (url of the post or comment),
+    /// recording the attempt for [`EmoteResolver::take_coverage`].
+    pub fn resolve_emoji(&self, src: &str, alt: Option<&str>, source: &str) -> Option<Emoji> {
+        let (resolution, emoji) = self.resolve(src, alt);
+        self.record(resolution, src, source);
+        emoji
+    }
+
+    fn resolve(&self, src: &str, alt: Option<&str>) -> (Resolution, Option<Emoji>) {
         let alt = alt.filter(|a| !a.trim().is_empty());
 
         // unicode emoji
         if src.contains("emoji_u") {
             if let Some(c) = alt {
-                return Some(c.to_string());
+                return (Resolution::Unicode, Some(Emoji::Unicode(c.to_string())));
             }
 
             // backup
             let code = src
                 .split('/')
                 .next_back()
-                .unwrap()
-                .split('.')
-                .next()
-                .unwrap()
+                .and_then(|name| name.split('.').next())
+                .unwrap_or_default()
                 .trim_start_matches("emoji_u");
-            let result = code
+            return code
                 .split('_')
-                .map(|c| std::char::from_u32(u32::from_str_radix(c, 16).unwrap()).unwrap())
-                .collect::<String>();
-            return Some(result);
+                .map(|c| std::char::from_u32(u32::from_str_radix(c, 16).ok()?))
+                .collect::<Option<String>>()
+                .map_or((Resolution::Unresolved, None), |emoji| {
+                    (Resolution::Unicode, Some(Emoji::Unicode(emoji)))
+                });
         }
 
         // custom emoji
         if let Some(c) = alt {
-            return Some(format!(":_{}:", c));
+            return (Resolution::AltText, Some(Emoji::Custom(c.to_string())));
         }
 
         // backup
-        let id = src
-            .split("/")
-            .last()
-            .unwrap()
-            .split(".")
-            .next()
-            .unwrap()
-            .split_inclusive("=")
-            .next()
-            .unwrap();
-        self.mapping.get(id).map(|name| format!(":_{}:", name))
+        match emote_id(src).and_then(|id| self.mapping.get(id)) {
+            Some(name) => (Resolution::Mapping, Some(Emoji::Custom(name.clone()))),
+            None => (Resolution::Unresolved, None),
+        }
     }
+
+    fn record(&self, resolution: Resolution, src: &str, source: &str) {
+        let mut coverage = self.coverage.lock().unwrap();
+        *coverage.counts.entry(resolution).or_default() += 1;
+        if resolution == Resolution::Unresolved {
+            let id = emote_id(src).filter(|_| !src.contains("emoji_u"));
+            coverage
+                .unresolved
+                .entry(id.unwrap_or(src).to_owned())
+                .or_default()
+                .insert(source.to_owned());
+        }
+    }
+
+    /// Record the emotes of a post parsed by an earlier run, ie. from the parse cache.
+    /// Custom emotes are counted as resolved by the mapping if their name matches it, by alt text otherwise.
+    pub fn record_post(&self, post: &Post) {
+        let record_text = |text: &RichText, source: &str| {
+            for span in &text.0 {
+                match span {
+                    Span::Emoji { .. } => {
+                        self.record(Resolution::Unicode, "", source);
+                    }
+                    Span::Emote { name: None, url } => {
+                        self.record(Resolution::Unresolved, url, source);
+                    }
+                    Span::Emote {
+                        name: Some(name),
+                        url,
+                    } => {
+                        let mapped = emote_id(url).and_then(|id| self.mapping.get(id));
+                        let resolution = if mapped == Some(name) {
+                            Resolution::Mapping
+                        } else {
+                            Resolution::AltText
+                        };
+                        self.record(resolution, url, source);
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        fn visit_threads(threads: &[CommentThread], record_text: &impl Fn(&RichText, &str)) {
+            for thread in threads {
+                record_text(&thread.comment.content, &thread.comment.url);
+                visit_threads(&thread.replies, record_text);
+            }
+        }
+
+        record_text(&post.main.content, &post_url(&post.id));
+        if let Some(comments) = &post.comments {
+            visit_threads(comments, &record_text);
+        }
+    }
+
+    /// Take the emote resolutions recorded so far.
+    pub fn take_coverage(&self) -> EmoteCoverage {
+        std::mem::take(&mut self.coverage.lock().unwrap())
+    }
+}
+
+/// Relative url of a post, same form as comment urls.
+pub fn post_url(id: &str) -> String {
+    format!("/post/{id}")
 }
 
-fn load_emoji_mapping(emote_dir: &Path) -> HashMap<String, String> {
-    let default: HashMap<String, String> =
-        match File::open(emote_dir.join("emote_mapping_default.json")) {
-            Ok(fs) => serde_json::from_reader(fs).unwrap(),
-            Err(err) if err.kind() == io::ErrorKind::NotFound => Default::default(),
-            Err(err) => panic!("{}", err),
+/// How an emoji image was resolved.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Unicode,
+    /// Custom emote named by its alt text.
+    AltText,
+    /// Custom emote without alt text, named by the mapping.
+    Mapping,
+    Unresolved,
+}
+
+/// Emote resolutions recorded while parsing.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct EmoteCoverage {
+    /// Number of resolved images by how they were resolved.
+    pub counts: BTreeMap<Resolution, usize>,
+    /// Unresolved emote ids (image url for unicode emoji) with urls of the posts and comments using them.
+    pub unresolved: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Id of a custom emote from its image url, ie. `<id>=` of `https://yt3.ggpht.com/<id>=s48-c-k-nd`.
+pub fn emote_id(src: &str) -> Option<&str> {
+    src.split('/')
+        .next_back()?
+        .split('.')
+        .next()?
+        .split_inclusive('=')
+        .next()
+}
+
+/// Emote image downloaded by [`EmoteResolver::sync`], keyed by name in `manifest.json`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncedEmote {
+    pub id: String,
+    /// Image size in pixels.
+    pub size: u32,
+    pub blake3: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Names of the downloaded emotes.
+    pub downloaded: Vec<String>,
+    /// Names of the emotes already up to date.
+    pub skipped: Vec<String>,
+    /// Emote names with the reason they failed.
+    pub failed: Vec<(String, String)>,
+}
+
+impl EmoteResolver {
+    /// Download every mapped custom emote at `size` pixels into `dir` as `<name>.png`,
+    /// skipping images whose id, size and hash match `dir/manifest.json`.
+    pub fn sync(
+        &self,
+        agent: &ureq::Agent,
+        base_url: &Url,
+        dir: &Path,
+        size: u32,
+    ) -> Result<SyncReport> {
+        fs::create_dir_all(dir).wrap_err_with(|| format!("fail to create {}", dir.display()))?;
+        let manifest_file = dir.join("manifest.json");
+        let mut manifest: BTreeMap<String, SyncedEmote> = match File::open(&manifest_file) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .wrap_err_with(|| format!("fail to parse {}", manifest_file.display()))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
         };
 
-    let custom: HashMap<String, String> = match File::open(emote_dir.join("emote_mapping.json")) {
-        Ok(fs) => serde_json::from_reader(fs).unwrap(),
-        Err(err) if err.kind() == io::ErrorKind::NotFound => Default::default(),
-        Err(err) => panic!("{}", err),
-    };
+        let mut by_name = BTreeMap::<&str, Vec<&str>>::new();
+        for (id, name) in &self.mapping {
+            by_name.entry(name).or_default().push(id);
+        }
+
+        let mut report = SyncReport::default();
+        let results = by_name
+            .into_par_iter()
+            .map(|(name, ids)| {
+                let result = match &ids[..] {
+                    _ if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') => {
+                        Err(eyre!("name is not a valid file name"))
+                    }
+                    [id] => {
+                        let path = dir.join(format!("{name}.png"));
+                        let synced = manifest.get(name).is_some_and(|synced| {
+                            synced.id == *id
+                                && synced.size == size
+                                && hash_file(&path).is_ok_and(|hash| hash == synced.blake3)
+                        });
+                        if synced {
+                            Ok(None)
+                        } else {
+                            download_emote(agent, base_url, id, size, &path).map(|blake3| {
+                                Some(SyncedEmote {
+                                    id: id.to_string(),
+                                    size,
+                                    blake3,
+                                })
+                            })
+                        }
+                    }
+                    ids => Err(eyre!(
+                        "name is used by multiple ids: {}",
+                        ids.iter()
+                            .sorted()
+                            .map(|id| match self.source(id) {
+                                Some(source) => format!("{id} ({})", source.display()),
+                                None => id.to_string(),
+                            })
+                            .join(", ")
+                    )),
+                };
+                (name, result)
+            })
+            .collect::<Vec<_>>();
+
+        for (name, result) in results {
+            match result {
+                Ok(Some(synced)) => {
+                    manifest.insert(name.to_owned(), synced);
+                    report.downloaded.push(name.to_owned());
+                }
+                Ok(None) => report.skipped.push(name.to_owned()),
+                Err(err) => report.failed.push((name.to_owned(), format!("{err:#}"))),
+            }
+        }
 
-    default.into_iter().chain(custom).collect()
+        let mut writer = BufWriter::new(File::create(&manifest_file)?);
+        serde_json::to_writer_pretty(&mut writer, &manifest)?;
+        writer.flush()?;
+        Ok(report)
+    }
+}
+
+/// Download emote image into `path`, returning its hash.
+fn download_emote(
+    agent: &ureq::Agent,
+    base_url: &Url,
+    id: &str,
+    size: u32,
+    path: &Path,
+) -> Result<String> {
+    let url = base_url.join(&format!("{id}s{size}-c-k-nd"))?;
+    let mut response = agent.get(url.as_str()).call()?;
+    if response.status() != 200 {
+        bail!("{url}: HTTP {}", response.status().as_u16());
+    }
+    let mut image = Vec::new();
+    response
+        .body_mut()
+        .as_reader()
+        .read_to_end(&mut image)
+        .wrap_err_with(|| format!("fail to download {url}"))?;
+
+    let part = path.with_extension("png.part");
+    fs::write(&part, &image)?;
+    fs::rename(&part, path)?;
+    Ok(blake3::hash(&image).to_hex().to_string())
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Whether `id` looks like a custom emote id, ie. `FrYgdeZPpvXs-6Mp305Z=`.
+fn is_emote_id(id: &str) -> bool {
+    id.strip_suffix('=').is_some_and(|id| {
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
 }
 
 #[cfg(test)]
@@ -96,57 +445,238 @@ mod tests {
         assert_eq!(
             resolver.resolve_emoji(
                 "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f499.png",
-                Some("💙")
+                Some("💙"),
+                "/post/test"
             ),
-            Some("💙".to_string())
+            Some(Emoji::Unicode("💙".to_string()))
         );
 
         assert_eq!(
             resolver.resolve_emoji(
                 "https://yt3.googleusercontent.com/9CkO5FMttx7Cx-6HUnNQZ6RhhddVL4oBzrCX_A3kUYDL0nKVWCfwYp49_w3mjgSn7oBey3dxerU=s16-w24-h24-c-k-nd",
-                Some("Rawr")
+                Some("Rawr"),
+                "/post/test"
             ),
-            Some(":_Rawr:".to_string())
+            Some(Emoji::Custom("Rawr".to_string()))
         );
 
         assert_eq!(
             resolver.resolve_emoji(
                 "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f499.png",
-                None
+                None,
+                "/post/test"
             ),
-            Some("💙".to_string())
+            Some(Emoji::Unicode("💙".to_string()))
         );
 
         assert_eq!(
             resolver.resolve_emoji(
                 "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f647_200d_2642.png",
-                None
+                None,
+                "/post/test"
             ),
-            Some("🙇‍♂".to_string())
+            Some(Emoji::Unicode("🙇‍♂".to_string()))
         );
 
         assert_eq!(
             resolver.resolve_emoji(
                 "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f64c_1f3fb.png",
-                None
+                None,
+                "/post/test"
             ),
-            Some("🙌🏻".to_string())
+            Some(Emoji::Unicode("🙌🏻".to_string()))
         );
 
         assert_eq!(
             resolver.resolve_emoji(
                 "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f64f_1f3fc.png",
-                None
+                None,
+                "/post/test"
             ),
-            Some("🙏🏼".to_string())
+            Some(Emoji::Unicode("🙏🏼".to_string()))
         );
 
         assert_eq!(
             resolver.resolve_emoji(
                 "https://lh3.googleusercontent.com/FrYgdeZPpvXs-6Mp305ZiimWJ0wV5bcVZctaUy80mnIdwe-P8HRGYAm0OyBtVx8EB9_Dxkc=s16-w24-h24-c-k-nd",
-                None
+                None,
+                "/post/test"
             ),
-            Some(":_eyes-purple-crying:".to_string())
+            Some(Emoji::Custom("eyes-purple-crying".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_coverage() {
+        let resolver = EmoteResolver::with_mapping([("mapped=".to_owned(), "a".to_owned())].into());
+        let src = |id: &str| format!("https://yt3.ggpht.com/{id}s16-w24-h24-c-k-nd");
+
+        resolver.resolve_emoji(&src("alt="), Some("b"), "/post/1");
+        resolver.resolve_emoji(&src("mapped="), None, "/post/1");
+        resolver.resolve_emoji(&src("unknown="), None, "/post/1?lc=1");
+        resolver.resolve_emoji(&src("unknown="), None, "/post/1?lc=2");
+        resolver.resolve_emoji(&src("unknown="), Some(" "), "/post/1?lc=2");
+        resolver.resolve_emoji(
+            "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f499.png",
+            None,
+            "/post/2",
+        );
+        resolver.resolve_emoji(
+            "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_uxyz.png",
+            None,
+            "/post/2",
+        );
+
+        let coverage = resolver.take_coverage();
+        insta::assert_json_snapshot!(serde_json::to_value(coverage).unwrap(), @r#"
+        {
+          "counts": {
+            "alt_text": 1,
+            "mapping": 1,
+            "unicode": 1,
+            "unresolved": 4
+          },
+          "unresolved": {
+            "https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_uxyz.png": [
+              "/post/2"
+            ],
+            "unknown=": [
+              "/post/1?lc=1",
+              "/post/1?lc=2"
+            ]
+          }
+        }
+        "#);
+        assert!(resolver.take_coverage().counts.is_empty());
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, json: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, json).unwrap();
+            path
+        };
+
+        // missing files in data dir are fine
+        let resolver = EmoteResolver::load(dir.path(), &[]).unwrap();
+        assert!(resolver.mapping().is_empty());
+
+        write("emote_mapping_default.json", r#"{"a=": "a", "b=": "b"}"#);
+        write("emote_mapping.json", r#"{"b=": "b", "c-_1=": "c"}"#);
+        let extra = write("extra.json", r#"{"d=": "d"}"#);
+        let resolver = EmoteResolver::load(dir.path(), std::slice::from_ref(&extra)).unwrap();
+        assert_eq!(resolver.mapping().len(), 4);
+        assert_eq!(
+            resolver.source("b="),
+            Some(dir.path().join("emote_mapping_default.json").as_path())
+        );
+        assert_eq!(
+            resolver.source("c-_1="),
+            Some(dir.path().join("emote_mapping.json").as_path())
+        );
+        assert_eq!(resolver.source("d="), Some(extra.as_path()));
+
+        // extra files are required
+        let err = EmoteResolver::load(dir.path(), &[dir.path().join("missing.json")]).unwrap_err();
+        assert!(
+            err.to_string().starts_with("fail to read emote mapping"),
+            "{err}"
+        );
+
+        // later files win over conflicting names
+        write("emote_mapping.json", r#"{"a=": "A"}"#);
+        let extra = write("extra.json", r#"{"a=": "AA"}"#);
+        let resolver = EmoteResolver::load(dir.path(), std::slice::from_ref(&extra)).unwrap();
+        assert_eq!(resolver.mapping()["a="], "AA");
+        assert_eq!(resolver.source("a="), Some(extra.as_path()));
+        let conflicts = resolver
+            .conflicts()
+            .join("\n")
+            .replace(&format!("{}/", dir.path().display()), "");
+        insta::assert_snapshot!(conflicts, @"
+        `a=` is `a` in emote_mapping_default.json but `A` in emote_mapping.json, using `A`
+        `a=` is `A` in emote_mapping.json but `AA` in extra.json, using `AA`
+        ");
+
+        write("emote_mapping.json", r#"{"b": "b", "c=": " "}"#);
+        let err = EmoteResolver::load(dir.path(), &[]).unwrap_err();
+        let err = err
+            .to_string()
+            .replace(&format!("{}/", dir.path().display()), "");
+        insta::assert_snapshot!(err, @"
+        2 problems in emote mappings:
+          - emote_mapping.json: `b` is not an emote id
+          - emote_mapping.json: `c=` has an empty name
+        ");
+
+        write("emote_mapping.json", "[]");
+        let err = EmoteResolver::load(dir.path(), &[]).unwrap_err();
+        assert!(
+            err.to_string().starts_with("fail to parse emote mapping"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_emote_id() {
+        assert_eq!(
+            emote_id("https://yt3.ggpht.com/FrYgdeZPpvXs-6Mp305Z=s16-w24-h24-c-k-nd"),
+            Some("FrYgdeZPpvXs-6Mp305Z=")
+        );
+    }
+
+    #[test]
+    fn test_sync() {
+        use crate::http::{
+            agent,
+            test_server::{Routes, serve},
+        };
+
+        let (base_url, log) = serve(Routes::from([
+            ("/abc=s240-c-k-nd", vec![(200, b"A".as_slice())]),
+            ("/dup1=s240-c-k-nd", vec![(200, b"D".as_slice())]),
+        ]));
+        let resolver = EmoteResolver::with_mapping(
+            [
+                ("abc=", "a"),
+                ("missing=", "b"),
+                ("dup1=", "dup"),
+                ("dup2=", "dup"),
+            ]
+            .map(|(id, name)| (id.to_owned(), name.to_owned()))
+            .into(),
+        );
+        let dir = tempfile::tempdir().unwrap();
+
+        let report = resolver.sync(&agent(), &base_url, dir.path(), 240).unwrap();
+        assert_eq!(report.downloaded, ["a"]);
+        assert_eq!(
+            report
+                .failed
+                .iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["b", "dup"]
+        );
+        assert_eq!(fs::read(dir.path().join("a.png")).unwrap(), b"A");
+
+        // up to date
+        let report = resolver.sync(&agent(), &base_url, dir.path(), 240).unwrap();
+        assert_eq!(report.skipped, ["a"]);
+
+        // changed file is downloaded again
+        fs::write(dir.path().join("a.png"), b"corrupted").unwrap();
+        let report = resolver.sync(&agent(), &base_url, dir.path(), 240).unwrap();
+        assert_eq!(report.downloaded, ["a"]);
+        assert_eq!(fs::read(dir.path().join("a.png")).unwrap(), b"A");
+
+        let log = log.lock().unwrap();
+        assert_eq!(
+            log.iter().filter(|r| r.starts_with("/abc=")).count(),
+            2,
+            "{log:?}"
         );
     }
 }
@@ -0,0 +1,212 @@
+use scraper::{ElementRef, Selector};
+use std::fmt;
+
+/// Maximum length (in bytes) of the html excerpt attached to a parse error.
+const EXCERPT_LEN: usize = 300;
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// Failure to parse a post, with enough context to locate the offending markup.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ParseError {
+    pub post_id: Option<String>,
+    pub message: String,
+    /// Selector that failed to match (or matched unexpectedly).
+    pub selector: Option<String>,
+    /// Html of the element the selector was applied on, truncated.
+    pub excerpt: Option<String>,
+    /// Where in the post the error occurred, outer-most first (ie. `comment thread [3]`).
+    pub context: Vec<String>,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            post_id: None,
+            message: message.into(),
+            selector: None,
+            excerpt: None,
+            context: Vec::new(),
+        }
+    }
+
+    /// Error while applying `selector` on `element`.
+    pub fn at(element: ElementRef<'_>, selector: &str, message: impl Into<String>) -> Self {
+        Self {
+            selector: Some(selector.to_owned()),
+            ..Self::new(message).with_excerpt(element)
+        }
+    }
+
+    pub fn with_excerpt(mut self, element: ElementRef<'_>) -> Self {
+        self.excerpt = Some(excerpt(&element.html()));
+        self
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context.insert(0, context.into());
+        self
+    }
+
+    pub fn with_post_id(mut self, post_id: impl Into<String>) -> Self {
+        self.post_id = Some(post_id.into());
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(post_id) = &self.post_id {
+            write!(f, "post {post_id}: ")?;
+        }
+        for context in &self.context {
+            write!(f, "{context}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(selector) = &self.selector {
+            write!(f, " (selector `{selector}`)")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub trait ParseResultExt<T> {
+    fn context_with(self, f: impl FnOnce() -> String) -> ParseResult<T>;
+}
+
+impl<T> ParseResultExt<T> for ParseResult<T> {
+    fn context_with(self, f: impl FnOnce() -> String) -> ParseResult<T> {
+        self.map_err(|err| err.with_context(f()))
+    }
+}
+
+fn excerpt(html: &str) -> String {
+    if html.len() <= EXCERPT_LEN {
+        return html.to_owned();
+    }
+    let end = (0..=EXCERPT_LEN)
+        .rev()
+        .find(|&i| html.is_char_boundary(i))
+        .unwrap_or(0);
+    format!("{}…", &html[..end])
+}
+
+fn selector(selector: &str) -> Selector {
+    Selector::parse(selector).expect("selectors are hardcoded and should be valid")
+}
+
+/// Select exactly one element matching `selector`.
+pub fn select_one<'a>(element: ElementRef<'a>, selector_str: &str) -> ParseResult<ElementRef<'a>> {
+    let selector = selector(selector_str);
+    let mut matches = element.select(&selector);
+    let first = matches.next().ok_or_else(|| {
+        ParseError::at(
+            element,
+            selector_str,
+            "expected exactly one element, found none",
+        )
+    })?;
+    let rest = matches.count();
+    if rest > 0 {
+        return Err(ParseError::at(
+            element,
+            selector_str,
+            format!("expected exactly one element, found {}", rest + 1),
+        ));
+    }
+    Ok(first)
+}
+
+/// Select at most one element matching `selector`.
+pub fn select_at_most_one<'a>(
+    element: ElementRef<'a>,
+    selector_str: &str,
+) -> ParseResult<Option<ElementRef<'a>>> {
+    let selector = selector(selector_str);
+    let mut matches = element.select(&selector);
+    let first = matches.next();
+    let rest = matches.count();
+    if rest > 0 {
+        return Err(ParseError::at(
+            element,
+            selector_str,
+            format!("expected at most one element, found {}", rest + 1),
+        ));
+    }
+    Ok(first)
+}
+
+/// Select the first element matching `selector`.
+pub fn select_first<'a>(
+    element: ElementRef<'a>,
+    selector_str: &str,
+) -> ParseResult<ElementRef<'a>> {
+    element
+        .select(&selector(selector_str))
+        .next()
+        .ok_or_else(|| {
+            ParseError::at(
+                element,
+                selector_str,
+                "expected at least one element, found none",
+            )
+        })
+}
+
+/// Select every element matching `selector`.
+pub fn select_all<'a>(element: ElementRef<'a>, selector_str: &str) -> Vec<ElementRef<'a>> {
+    element.select(&selector(selector_str)).collect()
+}
+
+/// Get attribute `name` of `element`.
+pub fn attr<'a>(element: ElementRef<'a>, name: &str) -> ParseResult<&'a str> {
+    element.attr(name).ok_or_else(|| {
+        ParseError::new(format!(
+            "<{}> should have `{name}` attribute",
+            element.value().name()
+        ))
+        .with_excerpt(element)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let html = scraper::Html::parse_fragment(
+            r#"<div><p class="a">1</p><p class="a">2</p><p id="b">3</p></div>"#,
+        );
+        let root = html.root_element();
+
+        assert_eq!(select_one(root, "#b").unwrap().inner_html(), "3");
+        assert!(select_at_most_one(root, "#c").unwrap().is_none());
+        assert_eq!(select_first(root, ".a").unwrap().inner_html(), "1");
+
+        let err = select_one(root, ".a").unwrap_err();
+        assert_eq!(err.selector.as_deref(), Some(".a"));
+        assert_eq!(err.message, "expected exactly one element, found 2");
+        assert!(err.excerpt.unwrap().contains(r#"<p id="b">3</p>"#));
+
+        let err = select_at_most_one(root, ".a")
+            .context_with(|| "comment [1]".to_owned())
+            .unwrap_err()
+            .with_context("comment thread [0]")
+            .with_post_id("Ugkx");
+        assert_eq!(
+            err.to_string(),
+            "post Ugkx: comment thread [0]: comment [1]: expected at most one element, found 2 (selector `.a`)"
+        );
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let html = "ก".repeat(200);
+        let excerpt = excerpt(&html);
+        assert!(excerpt.len() <= EXCERPT_LEN + '…'.len_utf8());
+        assert!(excerpt.ends_with('…'));
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+/// Agent for downloading assets, HTTP error statuses are returned as responses for the caller to handle.
+pub fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(60)))
+        .http_status_as_error(false)
+        .build()
+        .new_agent()
+}
+
+#[cfg(test)]
+pub mod test_server {
+    use std::{
+        collections::HashMap,
+        io::{self, BufRead, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+    };
+    use url::Url;
+
+    /// Responses of the stand-in server by path, consumed in order (the last one is repeated).
+    pub type Routes = HashMap<&'static str, Vec<(u16, &'static [u8])>>;
+
+    /// Minimal HTTP server with `Range` support, returns its base url and the log of requests.
+    pub fn serve(routes: Routes) -> (Url, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let routes = Arc::new(Mutex::new(routes));
+
+        let requests = log.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line
+                    .split(' ')
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_owned();
+                let mut offset = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(range) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        offset = range.trim().trim_end_matches('-').parse().unwrap();
+                    }
+                }
+
+                let (status, body) = {
+                    let mut routes = routes.lock().unwrap();
+                    match routes.get_mut(path.as_str()) {
+                        Some(responses) if responses.len() > 1 => responses.remove(0),
+                        Some(responses) => responses[0],
+                        None => (404, b"".as_slice()),
+                    }
+                };
+                let (status, body) = if status == 200 && offset > 0 {
+                    (206, &body[offset..])
+                } else {
+                    (status, body)
+                };
+                requests
+                    .lock()
+                    .unwrap()
+                    .push(format!("{path} {status} {offset}"));
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+                // drain so the client does not see a reset
+                let _ = reader.read(&mut [0; 1]);
+            }
+        });
+
+        (base_url, log)
+    }
+}
@@ -0,0 +1,324 @@
+use color_eyre::{
+    Result,
+    eyre::{Context, bail, ensure, eyre},
+};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, Lines, Write},
+};
+
+use crate::model::{Archive, PostWithMeta, SCHEMA_VERSION};
+
+/// First line of posts.jsonl, every following line is a post.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Header {
+    pub schema_version: u32,
+    pub parser_version: Option<u32>,
+    pub generated_at: jiff::Timestamp,
+}
+
+impl Header {
+    pub fn new(parser_version: Option<u32>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            parser_version,
+            generated_at: jiff::Timestamp::now(),
+        }
+    }
+}
+
+/// Write posts as JSON Lines in a given order while they are produced out of order,
+/// buffering only the posts that arrive ahead of their turn.
+#[derive(Debug)]
+pub struct OrderedWriter<W: Write> {
+    writer: W,
+    /// Ids not yet written, in order.
+    pending: VecDeque<String>,
+    /// Posts that arrived ahead of their turn, `None` for skipped ids.
+    buffered: HashMap<String, Option<PostWithMeta>>,
+    written: usize,
+}
+
+impl<W: Write> OrderedWriter<W> {
+    pub fn new(mut writer: W, header: &Header, order: Vec<String>) -> Result<Self> {
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self {
+            writer,
+            pending: order.into(),
+            buffered: HashMap::new(),
+            written: 0,
+        })
+    }
+
+    pub fn push(&mut self, post: PostWithMeta) -> Result<()> {
+        self.buffered.insert(post.post.id.clone(), Some(post));
+        self.write_ready()
+    }
+
+    /// Mark `id` as never coming, ie. it failed to parse.
+    pub fn skip(&mut self, id: &str) -> Result<()> {
+        self.buffered.insert(id.to_owned(), None);
+        self.write_ready()
+    }
+
+    fn write_ready(&mut self) -> Result<()> {
+        while let Some(id) = self.pending.front() {
+            match self.buffered.remove(id) {
+                Some(Some(post)) => self.write(&post)?,
+                Some(None) => {}
+                None => break,
+            }
+            self.pending.pop_front();
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, post: &PostWithMeta) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, post)?;
+        self.writer.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Number of posts held back waiting for an earlier one.
+    pub fn buffered(&self) -> usize {
+        self.buffered.len()
+    }
+
+    /// Number of posts written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Write the posts still buffered, in order then the ones missing from the order by id,
+    /// returning the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        for id in std::mem::take(&mut self.pending) {
+            if let Some(Some(post)) = self.buffered.remove(&id) {
+                self.write(&post)?;
+            }
+        }
+        let mut rest = std::mem::take(&mut self.buffered)
+            .into_values()
+            .flatten()
+            .collect::<Vec<_>>();
+        rest.sort_by(|a, b| a.post.id.cmp(&b.post.id));
+        for post in &rest {
+            self.write(post)?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Header of posts.jsonl of any schema version, for checking the version before reading posts.
+pub fn read_header(reader: impl BufRead) -> Result<Value> {
+    let header = reader
+        .lines()
+        .next()
+        .ok_or_else(|| eyre!("missing header"))??;
+    serde_json::from_str(&header).wrap_err("fail to parse header")
+}
+
+/// Read posts.jsonl of any schema version into the shape of a posts.json of the same version, for [`crate::migrate`].
+pub fn read_value(reader: impl BufRead) -> Result<Value> {
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or_else(|| eyre!("missing header"))??;
+    let Value::Object(mut archive) =
+        serde_json::from_str(&header).wrap_err("fail to parse header")?
+    else {
+        bail!("header is not an object");
+    };
+    let posts = lines
+        .enumerate()
+        .map(|(i, text)| {
+            let line = i + 2;
+            text.map_err(color_eyre::Report::from)
+                .and_then(|text| Ok(serde_json::from_str(&text)?))
+                .wrap_err_with(|| format!("fail to read post at line {line}"))
+        })
+        .collect::<Result<Vec<Value>>>()?;
+    archive.insert("channel".to_owned(), Value::Null);
+    archive.insert("posts".to_owned(), posts.into());
+    Ok(archive.into())
+}
+
+/// Write `archive` as posts.jsonl, keeping its header fields.
+pub fn write(mut writer: impl Write, archive: &Archive) -> Result<()> {
+    let header = Header {
+        schema_version: archive.schema_version,
+        parser_version: archive.parser_version,
+        generated_at: archive.generated_at,
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writer.write_all(b"\n")?;
+    for post in &archive.posts {
+        serde_json::to_writer(&mut writer, post)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read posts.jsonl one post at a time.
+#[derive(Debug)]
+pub struct Reader<R: BufRead> {
+    header: Header,
+    lines: Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Read the header, which must be of the current schema version.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut lines = reader.lines();
+        let header = lines.next().ok_or_else(|| eyre!("missing header"))??;
+        let header: Header = serde_json::from_str(&header).wrap_err("fail to parse header")?;
+        ensure!(
+            header.schema_version == SCHEMA_VERSION,
+            "schema version {} is not supported, expected {SCHEMA_VERSION}",
+            header.schema_version
+        );
+        Ok(Self {
+            header,
+            lines,
+            line: 1,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Read every post into an [`Archive`].
+    pub fn into_archive(self) -> Result<Archive> {
+        let Header {
+            schema_version,
+            parser_version,
+            generated_at,
+        } = self.header;
+        let posts = self.collect::<Result<Vec<_>>>()?;
+        Ok(Archive {
+            schema_version,
+            generated_at,
+            ..Archive::new(posts, parser_version)
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<PostWithMeta>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.lines.next()?;
+        self.line += 1;
+        let line = self.line;
+        Some(
+            text.map_err(color_eyre::Report::from)
+                .and_then(|text| Ok(serde_json::from_str(&text)?))
+                .wrap_err_with(|| format!("fail to read post at line {line}")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tests::post;
+
+    fn ids(jsonl: &[u8]) -> Vec<String> {
+        Reader::new(jsonl)
+            .unwrap()
+            .map(|post| post.unwrap().post.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_ordered_writer() {
+        let order = ["a", "b", "c", "d"].map(str::to_owned).to_vec();
+        let mut writer = OrderedWriter::new(Vec::new(), &Header::new(Some(1)), order).unwrap();
+
+        writer.push(post("c")).unwrap();
+        writer.push(post("b")).unwrap();
+        assert_eq!((writer.written(), writer.buffered()), (0, 2));
+        writer.push(post("a")).unwrap();
+        assert_eq!((writer.written(), writer.buffered()), (3, 0));
+        writer.push(post("x")).unwrap();
+        writer.skip("d").unwrap();
+        assert_eq!(writer.written(), 3);
+
+        let jsonl = writer.finish().unwrap();
+        assert_eq!(ids(&jsonl), ["a", "b", "c", "x"]);
+    }
+
+    #[test]
+    fn test_finish_with_missing() {
+        let order = ["a", "b", "c"].map(str::to_owned).to_vec();
+        let mut writer = OrderedWriter::new(Vec::new(), &Header::new(None), order).unwrap();
+        writer.push(post("c")).unwrap();
+        let jsonl = writer.finish().unwrap();
+        assert_eq!(ids(&jsonl), ["c"]);
+    }
+
+    #[test]
+    fn test_reader() {
+        let header = Header::new(Some(1));
+        let mut writer = OrderedWriter::new(Vec::new(), &header, vec!["a".to_owned()]).unwrap();
+        writer.push(post("a")).unwrap();
+        let jsonl = writer.finish().unwrap();
+
+        let reader = Reader::new(&jsonl[..]).unwrap();
+        assert_eq!(reader.header(), &header);
+        let archive = reader.into_archive().unwrap();
+        assert_eq!(archive.generated_at, header.generated_at);
+        assert_eq!(archive.parser_version, Some(1));
+        assert_eq!(archive.posts.len(), 1);
+
+        let mut broken = jsonl.clone();
+        broken.extend_from_slice(b"{}\n");
+        let err = Reader::new(&broken[..])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "fail to read post at line 3");
+
+        let header = serde_json::to_string(&Header {
+            schema_version: SCHEMA_VERSION + 1,
+            ..Header::new(None)
+        })
+        .unwrap();
+        assert!(Reader::new(header.as_bytes()).is_err());
+        assert!(Reader::new(&b""[..]).is_err());
+    }
+
+    #[test]
+    fn test_write_read_value() {
+        let archive = Archive::new(vec![post("a"), post("b")], Some(1));
+        let mut jsonl = Vec::new();
+        write(&mut jsonl, &archive).unwrap();
+
+        let header = read_header(&jsonl[..]).unwrap();
+        assert_eq!(header["schema_version"], SCHEMA_VERSION);
+
+        // an archive of the current version migrates as is
+        let (from, migrated) = crate::migrate::migrate(read_value(&jsonl[..]).unwrap()).unwrap();
+        assert_eq!(from, SCHEMA_VERSION);
+        assert_eq!(migrated.generated_at, archive.generated_at);
+        assert_eq!(migrated.parser_version, Some(1));
+        assert_eq!(
+            migrated
+                .posts
+                .iter()
+                .map(|p| &p.post.id)
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+
+        let mut broken = jsonl.clone();
+        broken.extend_from_slice(b"{\n");
+        let err = read_value(&broken[..]).unwrap_err();
+        assert_eq!(err.to_string(), "fail to read post at line 4");
+    }
+}
@@ -0,0 +1,41 @@
+//! Parse YouTube community posts saved as html into typed posts.
+//!
+//! ```no_run
+//! use community_archiver::{Archive, EmoteResolver, ParseContext, parse};
+//! use std::path::Path;
+//!
+//! # fn main() -> color_eyre::Result<()> {
+//! let resolver = EmoteResolver::load(Path::new("data"), &[])?;
+//! let html = std::fs::read_to_string("archive/Ugkx.html")?;
+//! let post = parse(&html, "Ugkx".to_owned(), &ParseContext::new(&resolver))?;
+//! println!("{}", post.main.content.to_plain());
+//!
+//! // posts.json written by `community-archiver process`
+//! let archive: Archive = serde_json::from_str(&std::fs::read_to_string("data/posts.json")?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod cmd;
+pub mod emote;
+pub mod error;
+#[doc(hidden)]
+pub mod http;
+pub mod jsonl;
+pub mod link;
+pub mod locale;
+pub mod membership;
+pub mod migrate;
+pub mod model;
+pub mod parse;
+pub mod publish_time;
+pub mod rich_text;
+pub mod rules;
+
+pub use emote::EmoteResolver;
+pub use error::{ParseError, ParseResult};
+pub use model::{Archive, Comment, CommentThread, Main, Post, PostWithMeta};
+pub use parse::{ParseContext, parse};
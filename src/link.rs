@@ -0,0 +1,351 @@
+use url::Url;
+
+/// Base of relative links in YouTube pages, ie. `/channel/UC...`.
+pub const YOUTUBE_ORIGIN: &str = "https://www.youtube.com";
+
+/// What a link in a post or comment points to.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LinkTarget {
+    Video {
+        id: String,
+    },
+    /// Video at a specific time, from `&t=`.
+    Timestamp {
+        video_id: String,
+        seconds: u32,
+    },
+    Short {
+        id: String,
+    },
+    Playlist {
+        id: String,
+    },
+    /// Channel by id, ie. `/channel/UC...`.
+    Channel {
+        id: String,
+    },
+    /// Channel by handle, ie. `/@name`.
+    Handle {
+        handle: String,
+    },
+    /// Community post.
+    Post {
+        id: String,
+    },
+    Hashtag {
+        tag: String,
+    },
+    /// Link outside of YouTube, with `youtube.com/redirect` unwrapped.
+    External {
+        url: String,
+    },
+    /// YouTube link of an unrecognized kind.
+    Other,
+}
+
+impl LinkTarget {
+    /// Classify `href` as found in the page, which may be relative to YouTube.
+    pub fn classify(href: &str) -> Self {
+        let Ok(url) = Url::parse(YOUTUBE_ORIGIN).and_then(|base| base.join(href)) else {
+            return Self::Other;
+        };
+        let Some(host) = url.host_str() else {
+            return Self::Other;
+        };
+        let host = host.trim_start_matches("www.").trim_start_matches("m.");
+        let query = |key: &str| {
+            url.query_pairs()
+                .find(|(k, v)| k == key && !v.is_empty())
+                .map(|(_, v)| v.into_owned())
+        };
+        let segments = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let video = |id: String| match query("t").as_deref().and_then(parse_seconds) {
+            Some(seconds) => Self::Timestamp {
+                video_id: id,
+                seconds,
+            },
+            None => Self::Video { id },
+        };
+
+        match host {
+            "youtu.be" => match segments[..] {
+                [id] => video(id.to_owned()),
+                _ => Self::Other,
+            },
+            "youtube.com" => match segments[..] {
+                ["watch"] => match query("v") {
+                    Some(id) => video(id),
+                    None => query("list").map_or(Self::Other, |id| Self::Playlist { id }),
+                },
+                ["live", id] => video(id.to_owned()),
+                ["shorts", id] => Self::Short { id: id.to_owned() },
+                ["playlist"] => query("list").map_or(Self::Other, |id| Self::Playlist { id }),
+                ["post", id, ..] => Self::Post { id: id.to_owned() },
+                // post link before `/post/` url, ie. `/channel/UC.../community?lb=Ugkx...`
+                ["channel", id, ..] => match query("lb") {
+                    Some(id) => Self::Post { id },
+                    None => Self::Channel { id: id.to_owned() },
+                },
+                ["hashtag", tag, ..] => Self::Hashtag {
+                    tag: percent_decode(tag),
+                },
+                ["redirect"] => query("q").map_or(Self::Other, |url| Self::External { url }),
+                [handle, ..] if handle.starts_with('@') => Self::Handle {
+                    handle: percent_decode(handle),
+                },
+                _ => Self::Other,
+            },
+            _ => Self::External {
+                url: href.to_owned(),
+            },
+        }
+    }
+
+    pub fn is_video(&self) -> bool {
+        matches!(
+            self,
+            Self::Video { .. } | Self::Timestamp { .. } | Self::Short { .. }
+        )
+    }
+}
+
+/// Ids of a comment, decoded from its permalink.
+#[derive(Debug, PartialEq)]
+pub struct CommentUrl {
+    pub post_id: String,
+    /// `lc` parameter, `<thread>.<reply>` for replies.
+    pub comment_id: String,
+    /// Id of the top-level comment a reply belongs to.
+    pub parent_comment_id: Option<String>,
+}
+
+impl CommentUrl {
+    /// Decode `/post/<post>?lc=<comment>` or, before `/post/` urls, `/channel/UC.../community?lc=<comment>&lb=<post>`.
+    /// `None` if `href` is not a comment's permalink.
+    pub fn parse(href: &str) -> Option<Self> {
+        let LinkTarget::Post { id: post_id } = LinkTarget::classify(href) else {
+            return None;
+        };
+        let url = Url::parse(YOUTUBE_ORIGIN)
+            .and_then(|base| base.join(href))
+            .ok()?;
+        let comment_id = url
+            .query_pairs()
+            .find(|(k, v)| k == "lc" && !v.is_empty())?
+            .1
+            .into_owned();
+        let parent_comment_id = comment_id
+            .split_once('.')
+            .map(|(parent, _)| parent.to_owned());
+        Some(Self {
+            post_id,
+            comment_id,
+            parent_comment_id,
+        })
+    }
+}
+
+/// Parse `t` parameter of a video link, ie. `83`, `83s` or `1h2m3s`.
+fn parse_seconds(t: &str) -> Option<u32> {
+    if let Ok(seconds) = t.parse() {
+        return Some(seconds);
+    }
+
+    let mut seconds = 0;
+    let mut n = String::new();
+    for c in t.chars() {
+        if c.is_ascii_digit() {
+            n.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        // out of range is treated as unparseable
+        seconds = n
+            .parse::<u32>()
+            .ok()?
+            .checked_mul(unit)
+            .and_then(|n| n.checked_add(seconds))?;
+        n.clear();
+    }
+    n.is_empty().then_some(seconds)
+}
+
+fn percent_decode(segment: &str) -> String {
+    url::form_urlencoded::parse(format!("x={segment}").as_bytes())
+        .next()
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_else(|| segment.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let video = |id: &str| LinkTarget::Video { id: id.to_owned() };
+        let cases = [
+            ("/watch?v=dQw4w9WgXcQ", video("dQw4w9WgXcQ")),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL123",
+                video("dQw4w9WgXcQ"),
+            ),
+            ("https://youtu.be/dQw4w9WgXcQ?si=abc", video("dQw4w9WgXcQ")),
+            // out of range time is dropped
+            ("/watch?v=dQw4w9WgXcQ&t=9999999h", video("dQw4w9WgXcQ")),
+            (
+                "https://www.youtube.com/live/dQw4w9WgXcQ",
+                video("dQw4w9WgXcQ"),
+            ),
+            (
+                "/watch?v=dQw4w9WgXcQ&t=83s",
+                LinkTarget::Timestamp {
+                    video_id: "dQw4w9WgXcQ".to_owned(),
+                    seconds: 83,
+                },
+            ),
+            (
+                "https://youtu.be/dQw4w9WgXcQ?t=1h2m3s",
+                LinkTarget::Timestamp {
+                    video_id: "dQw4w9WgXcQ".to_owned(),
+                    seconds: 3723,
+                },
+            ),
+            (
+                "https://youtube.com/shorts/abcdefghijk",
+                LinkTarget::Short {
+                    id: "abcdefghijk".to_owned(),
+                },
+            ),
+            (
+                "https://www.youtube.com/playlist?list=PL123",
+                LinkTarget::Playlist {
+                    id: "PL123".to_owned(),
+                },
+            ),
+            (
+                "/channel/UCIRjccPaf9aWkNu5cxXJEUg",
+                LinkTarget::Channel {
+                    id: "UCIRjccPaf9aWkNu5cxXJEUg".to_owned(),
+                },
+            ),
+            (
+                "/@AmaneKanata/community",
+                LinkTarget::Handle {
+                    handle: "@AmaneKanata".to_owned(),
+                },
+            ),
+            (
+                "/@%E0%B8%AD%E0%B8%A3",
+                LinkTarget::Handle {
+                    handle: "@อร".to_owned(),
+                },
+            ),
+            (
+                "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg",
+                LinkTarget::Post {
+                    id: "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR".to_owned(),
+                },
+            ),
+            (
+                "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                LinkTarget::Post {
+                    id: "Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A".to_owned(),
+                },
+            ),
+            (
+                "/hashtag/amanetime",
+                LinkTarget::Hashtag {
+                    tag: "amanetime".to_owned(),
+                },
+            ),
+            (
+                "https://www.youtube.com/redirect?event=backstage_event&redir_token=abc&q=https%3A%2F%2Fexample.com%2Fa%3Fb%3Dc",
+                LinkTarget::External {
+                    url: "https://example.com/a?b=c".to_owned(),
+                },
+            ),
+            (
+                "https://twitter.com/amanekanatach",
+                LinkTarget::External {
+                    url: "https://twitter.com/amanekanatach".to_owned(),
+                },
+            ),
+            ("/feed/subscriptions", LinkTarget::Other),
+            ("/watch", LinkTarget::Other),
+        ];
+        for (href, expected) in cases {
+            assert_eq!(LinkTarget::classify(href), expected, "{href}");
+        }
+    }
+
+    #[test]
+    fn test_comment_url() {
+        let cases = [
+            (
+                "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg",
+                Some((
+                    "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                    "UgylRbHxm0aafAMaD514AaABAg",
+                    None,
+                )),
+            ),
+            (
+                "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARF8_UOHFLV",
+                Some((
+                    "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                    "UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARF8_UOHFLV",
+                    Some("UgylRbHxm0aafAMaD514AaABAg"),
+                )),
+            ),
+            (
+                "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMOR-vpM9U&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                Some((
+                    "Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                    "UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMOR-vpM9U",
+                    Some("UgwDTW4THFsDyEAMYXN4AaABAg"),
+                )),
+            ),
+            ("/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR", None),
+            ("/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=Ugw", None),
+            ("/watch?v=dQw4w9WgXcQ&lc=Ugw", None),
+        ];
+        for (href, expected) in cases {
+            let ids = CommentUrl::parse(href);
+            assert_eq!(
+                ids.as_ref().map(|ids| (
+                    ids.post_id.as_str(),
+                    ids.comment_id.as_str(),
+                    ids.parent_comment_id.as_deref()
+                )),
+                expected,
+                "{href}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(parse_seconds("83"), Some(83));
+        assert_eq!(parse_seconds("83s"), Some(83));
+        assert_eq!(parse_seconds("2m"), Some(120));
+        assert_eq!(parse_seconds("1h2m3s"), Some(3723));
+        assert_eq!(parse_seconds("1h2"), None);
+        assert_eq!(parse_seconds("abc"), None);
+        assert_eq!(parse_seconds("9999999h"), None);
+        assert_eq!(parse_seconds("1193046h28m15s"), Some(u32::MAX));
+        assert_eq!(parse_seconds("1193046h28m16s"), None);
+    }
+}
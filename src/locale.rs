@@ -0,0 +1,409 @@
+use color_eyre::{
+    Result,
+    eyre::{ensure, eyre},
+};
+use scraper::{Html, Selector};
+
+use crate::publish_time::TimeUnit;
+
+/// UI language of the YouTube page the post was saved from.
+///
+/// Numbers (likes, votes, comment count) are rendered in a compact, language-specific form,
+/// so the locale must be known to parse them back.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Th,
+    En,
+    Ja,
+    Ko,
+}
+
+impl Locale {
+    pub const ALL: [Self; 4] = [Self::Th, Self::En, Self::Ja, Self::Ko];
+
+    /// Match a BCP 47 language tag (ie. `th-TH`, `en`, `ja-JP`) by its primary language subtag.
+    pub fn from_lang_tag(tag: &str) -> Option<Self> {
+        let lang = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match lang.as_str() {
+            "th" => Some(Self::Th),
+            "en" => Some(Self::En),
+            "ja" => Some(Self::Ja),
+            "ko" => Some(Self::Ko),
+            _ => None,
+        }
+    }
+
+    /// Detect locale from the page's `<html lang>`.
+    pub fn detect(html: &Html) -> Option<Self> {
+        html.select(&Selector::parse("html[lang]").unwrap())
+            .next()
+            .and_then(|e| e.attr("lang"))
+            .and_then(Self::from_lang_tag)
+    }
+
+    /// Magnitude suffixes of compact numbers, ie. `1.2K` or `1.2 พัน`.
+    fn magnitudes(self) -> &'static [(&'static str, f64)] {
+        match self {
+            Self::Th => &[("พัน", 1e3), ("หมื่น", 1e4), ("แสน", 1e5), ("ล้าน", 1e6)],
+            Self::En => &[
+                ("K", 1e3),
+                ("k", 1e3),
+                ("M", 1e6),
+                ("m", 1e6),
+                ("B", 1e9),
+                ("b", 1e9),
+            ],
+            Self::Ja => &[("千", 1e3), ("万", 1e4), ("億", 1e8)],
+            Self::Ko => &[("천", 1e3), ("만", 1e4), ("억", 1e8)],
+        }
+    }
+
+    /// Words surrounding the poll's total vote count, ie. `1,234 คะแนน` or `1,234 votes`.
+    fn vote_words(self) -> &'static [&'static str] {
+        match self {
+            Self::Th => &["คะแนน"],
+            Self::En => &["votes", "vote"],
+            Self::Ja => &["票"],
+            Self::Ko => &["투표", "회", "표"],
+        }
+    }
+
+    /// Unit words of relative time, ie. `20 ชั่วโมงที่ผ่านมา` or `20 hours ago`.
+    fn time_units(self) -> &'static [(&'static str, TimeUnit)] {
+        match self {
+            Self::Th => &[
+                ("วินาที", TimeUnit::Second),
+                ("นาที", TimeUnit::Minute),
+                ("ชั่วโมง", TimeUnit::Hour),
+                ("วัน", TimeUnit::Day),
+                ("สัปดาห์", TimeUnit::Week),
+                ("เดือน", TimeUnit::Month),
+                ("ปี", TimeUnit::Year),
+            ],
+            Self::En => &[
+                ("second", TimeUnit::Second),
+                ("minute", TimeUnit::Minute),
+                ("hour", TimeUnit::Hour),
+                ("day", TimeUnit::Day),
+                ("week", TimeUnit::Week),
+                ("month", TimeUnit::Month),
+                ("year", TimeUnit::Year),
+            ],
+            Self::Ja => &[
+                ("秒", TimeUnit::Second),
+                ("分", TimeUnit::Minute),
+                ("時間", TimeUnit::Hour),
+                ("日", TimeUnit::Day),
+                ("週間", TimeUnit::Week),
+                ("か月", TimeUnit::Month),
+                ("ヶ月", TimeUnit::Month),
+                ("カ月", TimeUnit::Month),
+                ("年", TimeUnit::Year),
+            ],
+            Self::Ko => &[
+                ("초", TimeUnit::Second),
+                ("분", TimeUnit::Minute),
+                ("시간", TimeUnit::Hour),
+                ("일", TimeUnit::Day),
+                ("주", TimeUnit::Week),
+                ("개월", TimeUnit::Month),
+                ("년", TimeUnit::Year),
+            ],
+        }
+    }
+
+    /// Text of the members-only badge of posts, ie. `สมาชิกเท่านั้น` or `Members only`.
+    fn members_only_labels(self) -> &'static [&'static str] {
+        match self {
+            Self::Th => &["สมาชิกเท่านั้น", "เฉพาะสมาชิก"],
+            Self::En => &["Members only", "Members-only"],
+            Self::Ja => &["メンバー限定", "メンバー専用"],
+            Self::Ko => &["회원 전용"],
+        }
+    }
+
+    /// Label of the comment badge of members who joined less than a month ago.
+    fn new_member_labels(self) -> &'static [&'static str] {
+        match self {
+            Self::Th => &["สมาชิกใหม่"],
+            Self::En => &["New member"],
+            Self::Ja => &["新規メンバー"],
+            Self::Ko => &["신규 회원"],
+        }
+    }
+
+    /// Tooltip of the badge next to an official artist channel's name, other badges are verified channels.
+    fn artist_labels(self) -> &'static [&'static str] {
+        match self {
+            Self::Th => &["ช่องศิลปินอย่างเป็นทางการ"],
+            Self::En => &["Official Artist Channel"],
+            Self::Ja => &["公式アーティスト チャンネル", "公式アーティストチャンネル"],
+            Self::Ko => &["공식 아티스트 채널"],
+        }
+    }
+
+    /// Marker after the publish time of edited comments, ie. `(แก้ไขแล้ว)` or `(edited)`.
+    fn edited_labels(self) -> &'static [&'static str] {
+        match self {
+            Self::Th => &["แก้ไขแล้ว"],
+            Self::En => &["edited"],
+            Self::Ja => &["編集済み"],
+            Self::Ko => &["수정됨"],
+        }
+    }
+
+    pub fn is_artist_badge(self, tooltip: &str) -> bool {
+        let tooltip = tooltip.to_ascii_lowercase();
+        self.artist_labels()
+            .iter()
+            .any(|label| tooltip.contains(&label.to_ascii_lowercase()))
+    }
+
+    /// Whether a comment's publish time text has the edited marker.
+    pub fn is_edited(self, publish_time: &str) -> bool {
+        self.edited_labels()
+            .iter()
+            .any(|label| publish_time.contains(label))
+    }
+
+    /// Text around the members-only label of a post badge (ie. the tier name), `None` if there is no label.
+    pub fn strip_members_only_label(self, text: &str) -> Option<String> {
+        // ascii lowercase keeps byte offsets
+        let lower = text.to_ascii_lowercase();
+        self.members_only_labels().iter().find_map(|label| {
+            let start = lower.find(&label.to_ascii_lowercase())?;
+            Some(format!(
+                "{}{}",
+                &text[..start],
+                &text[start + label.len()..]
+            ))
+        })
+    }
+
+    /// Whether a commenter's sponsor badge label is of a new member, ie. `สมาชิกใหม่`.
+    pub fn is_new_member(self, label: &str) -> bool {
+        let label = label.to_ascii_lowercase();
+        self.new_member_labels()
+            .iter()
+            .any(|new| label.contains(&new.to_ascii_lowercase()))
+    }
+
+    /// Parse membership duration of a sponsor badge label (ie. `สมาชิกมา 2 ปี` or `Member for 1 year, 2 months`) into months.
+    /// `None` if it has no duration or one too long to count.
+    pub fn parse_membership_months(self, label: &str) -> Option<u32> {
+        let mut months = None;
+        let mut text = label;
+        while let Some(start) = text.find(|c: char| c.is_ascii_digit()) {
+            text = &text[start..];
+            let end = text
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(text.len());
+            let n = text[..end].parse::<u32>().ok()?;
+            text = text[end..].trim_start();
+            let unit = self
+                .time_units()
+                .iter()
+                .find(|(word, _)| text.starts_with(word))
+                .map(|&(_, unit)| unit);
+            let per_unit = match unit {
+                Some(TimeUnit::Month) => 1,
+                Some(TimeUnit::Year) => 12,
+                _ => continue,
+            };
+            // overflow means a label not worth trusting
+            months = Some(n.checked_mul(per_unit)?.checked_add(months.unwrap_or(0))?);
+        }
+        months
+    }
+
+    /// Parse relative time (ie. `20 hours ago`) into its amount and unit.
+    /// Trailing text such as the edited marker is ignored.
+    pub fn parse_relative_time(self, text: &str) -> Option<(i64, TimeUnit)> {
+        let text = &text[text.find(|c: char| c.is_ascii_digit())?..];
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let n = text[..end].parse().ok()?;
+        let rest = text[end..].trim_start();
+        self.time_units()
+            .iter()
+            .find(|(word, _)| rest.starts_with(word))
+            .map(|&(_, unit)| (n, unit))
+    }
+
+    /// Parse compact number (ie. `1,234`, `1.2K`, `3.4万`) into integer.
+    pub fn parse_compact_number(self, s: &str) -> Result<u32> {
+        let s = s.trim().replace([',', ' ', '\u{a0}'], "");
+        for &(suffix, magnitude) in self.magnitudes() {
+            if let Some(n) = s.strip_suffix(suffix) {
+                let n = (n.parse::<f64>()? * magnitude).round();
+                // NaN fails the range check as well
+                ensure!(
+                    (0.0..=u32::MAX as f64).contains(&n),
+                    "number out of range: {s:?}"
+                );
+                return Ok(n as u32);
+            }
+        }
+        Ok(s.parse::<u32>()?)
+    }
+
+    /// Parse like count, empty text means no likes.
+    pub fn parse_vote(self, vote: &str) -> Result<u32> {
+        let vote = vote.trim();
+        if vote.is_empty() {
+            return Ok(0);
+        }
+        self.parse_compact_number(vote)
+    }
+
+    /// Parse poll's total vote text, with the surrounding words stripped.
+    pub fn parse_total_votes(self, text: &str) -> Result<u32> {
+        let mut s = text.trim();
+        for word in self.vote_words() {
+            s = s.trim_start_matches(word).trim_end_matches(word).trim();
+        }
+        self.parse_vote(s)
+            .map_err(|err| eyre!("{err}: {text:?} ({self:?})"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_lang_tag() {
+        let cases = [
+            ("th-TH", Some(Locale::Th)),
+            ("th", Some(Locale::Th)),
+            ("en-US", Some(Locale::En)),
+            ("en-GB", Some(Locale::En)),
+            ("ja-JP", Some(Locale::Ja)),
+            ("ko_KR", Some(Locale::Ko)),
+            ("de-DE", None),
+            ("", None),
+        ];
+        for (tag, expected) in cases {
+            assert_eq!(Locale::from_lang_tag(tag), expected, "{tag}");
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let html = Html::parse_document(
+            r#"<html lang="ja-JP"><body><div id="contents"></div></body></html>"#,
+        );
+        assert_eq!(Locale::detect(&html), Some(Locale::Ja));
+
+        let html = Html::parse_document(r#"<div id="contents"></div>"#);
+        assert_eq!(Locale::detect(&html), None);
+    }
+
+    #[test]
+    fn test_parse_compact_number() {
+        let cases: &[(Locale, &[(&str, u32)])] = &[
+            (
+                Locale::Th,
+                &[
+                    ("0", 0),
+                    ("300", 300),
+                    ("1,234", 1_234),
+                    ("1.2 พัน", 1_200),
+                    ("2.3พัน", 2_300),
+                    ("1.5 หมื่น", 15_000),
+                    ("3 แสน", 300_000),
+                    ("1.1 ล้าน", 1_100_000),
+                ],
+            ),
+            (
+                Locale::En,
+                &[
+                    ("300", 300),
+                    ("1,234", 1_234),
+                    ("1.2K", 1_200),
+                    ("15K", 15_000),
+                    ("2.3k", 2_300),
+                    ("1.1M", 1_100_000),
+                    ("1B", 1_000_000_000),
+                ],
+            ),
+            (
+                Locale::Ja,
+                &[
+                    ("300", 300),
+                    ("1,234", 1_234),
+                    ("1.2千", 1_200),
+                    ("1.5万", 15_000),
+                    ("12万", 120_000),
+                    ("1億", 100_000_000),
+                ],
+            ),
+            (
+                Locale::Ko,
+                &[
+                    ("300", 300),
+                    ("1,234", 1_234),
+                    ("1.2천", 1_200),
+                    ("1.5만", 15_000),
+                    ("12만", 120_000),
+                    ("1억", 100_000_000),
+                ],
+            ),
+        ];
+        for (locale, cases) in cases {
+            for &(input, expected) in *cases {
+                assert_eq!(
+                    locale.parse_compact_number(input).unwrap(),
+                    expected,
+                    "{locale:?} {input:?}"
+                );
+            }
+        }
+
+        assert!(Locale::En.parse_compact_number("1.2 พัน").is_err());
+        assert!(Locale::Th.parse_compact_number("1.2K").is_err());
+        for input in ["5B", "-1K", "NaNK", "infK", "4,294,967,296"] {
+            assert!(Locale::En.parse_compact_number(input).is_err(), "{input:?}");
+        }
+        assert_eq!(
+            Locale::En.parse_compact_number("4.2B").unwrap(),
+            4_200_000_000
+        );
+    }
+
+    #[test]
+    fn test_parse_total_votes() {
+        let cases = [
+            (Locale::Th, "1,234 คะแนน", 1_234),
+            (Locale::Th, "1.2 พัน คะแนน", 1_200),
+            (Locale::En, "1,234 votes", 1_234),
+            (Locale::En, "1 vote", 1),
+            (Locale::En, "1.2K votes", 1_200),
+            (Locale::Ja, "1,234 票", 1_234),
+            (Locale::Ja, "1.5万 票", 15_000),
+            (Locale::Ko, "투표 1,234회", 1_234),
+            (Locale::Ko, "1.5만표", 15_000),
+        ];
+        for (locale, input, expected) in cases {
+            assert_eq!(
+                locale.parse_total_votes(input).unwrap(),
+                expected,
+                "{locale:?} {input:?}"
+            );
+        }
+    }
+}
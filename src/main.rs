@@ -1,804 +1,55 @@
-mod emote;
-
-use clap::Parser;
-use color_eyre::{
-    Result, Section, SectionExt,
-    eyre::{Context, ContextCompat, ensure, eyre},
-};
-use emote::EmoteResolver;
-use indicatif::{HumanBytes, ParallelProgressIterator, ProgressStyle};
-use itertools::Itertools;
-use rayon::prelude::*;
-use scraper::{Element, ElementRef, Node, Selector};
-use std::{
-    fs::{self, File},
-    io::{self, BufWriter, Write},
-    ops::Not,
-    path::PathBuf,
-    sync::{
-        OnceLock,
-        atomic::{AtomicU64, Ordering},
-    },
-    time::{Instant, SystemTime},
-};
+use clap::{Parser, Subcommand};
+use color_eyre::Result;
+use community_archiver::cmd::{self, Common};
+use std::process::ExitCode;
 
 #[derive(Parser)]
-struct Args {
-    #[arg(long, value_name = "DIR", default_value = "archive")]
-    archive_dir: PathBuf,
-    #[arg(long, value_name = "DIR", default_value = "data")]
-    /// Directory containing emote data (emote_mapping.json and emotes_default_mapping.json).
-    /// Use for fallback emote resolution.
-    emote_data_dir: PathBuf,
-    #[arg(long, value_name = "FILE", default_value = "data/post_ids.json")]
-    /// Path to post ids.json file, use for ordering the output.
-    post_ids_file: PathBuf,
-    #[arg(long, value_name = "FILE", default_value = "data/posts.json")]
-    output_file: PathBuf,
-}
-
-static EMOTE_RESOLVER: OnceLock<EmoteResolver> = OnceLock::new();
-
-fn main() -> Result<()> {
+#[command(version, about = "Archive and process YouTube community posts")]
+struct Cli {
+    #[command(flatten)]
+    common: Common,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Parse downloaded posts in the archive into posts.json.
+    Process(cmd::process::Args),
+    /// Validate downloaded posts html, to identify posts that need re-downloading.
+    Check(cmd::check::Args),
+    /// Compare post ids against the downloaded posts in the archive.
+    IdsCheck(cmd::ids_check::Args),
+    /// Download attachment images and sponsor badges of processed posts.
+    Images(cmd::images::Args),
+    /// Inspect emote mapping.
+    #[command(subcommand)]
+    Emotes(cmd::emotes::Command),
+    /// Export processed posts into other formats.
+    #[command(subcommand)]
+    Export(cmd::export::Command),
+    /// Print statistics of processed posts.
+    Stats(cmd::stats::Args),
+    /// Upgrade posts.json or posts.jsonl written by an earlier version to the current schema.
+    Migrate(cmd::migrate::Args),
+    /// Print the JSON Schema of posts.json.
+    Schema,
+}
+
+fn main() -> Result<ExitCode> {
     color_eyre::install()?;
 
-    let Args {
-        archive_dir,
-        emote_data_dir,
-        post_ids_file,
-        output_file,
-    } = Args::parse();
-    println!("Processing posts in `{}`", archive_dir.display());
-
-    let t0 = Instant::now();
-    let dirs = fs::read_dir(archive_dir)?.collect::<io::Result<Vec<_>>>()?;
-    if dirs.is_empty() {
-        println!("No posts to process");
-        return Ok(());
-    }
-
-    EMOTE_RESOLVER
-        .set(EmoteResolver::with_emote_dir(&emote_data_dir))
-        .unwrap();
-
-    let total_bytes = AtomicU64::new(0);
-    let mut posts = dirs
-        .into_par_iter()
-        .progress()
-        .with_style(ProgressStyle::with_template(
-            "{wide_bar} {pos}/{len} {per_sec} {eta}",
-        )?)
-        .map(|dir| {
-            let t0 = Instant::now();
-            let content = fs::read_to_string(dir.path())?;
-            let name = dir
-                .path()
-                .file_stem()
-                .context("no file name")?
-                .to_string_lossy()
-                .to_string();
-            total_bytes.fetch_add(content.len() as u64, Ordering::Relaxed);
-
-            let metadata = dir.metadata().ok();
-            let created_at = metadata.as_ref().and_then(|m| m.created().ok());
-            let modified_at = metadata.as_ref().and_then(|m| m.modified().ok());
-            let processed_at = SystemTime::now();
-
-            let post = parse(&content, name.clone())
-                .wrap_err_with(|| format!("fail to parse post from {name}"))?;
-            let elapsed = t0.elapsed();
-
-            let meta = Meta {
-                source_created_at: created_at.map(|t| t.try_into().unwrap()),
-                source_modified_at: modified_at.map(|t| t.try_into().unwrap()),
-                processed_at: processed_at.try_into().unwrap(),
-                process_time: elapsed.try_into().unwrap(),
-            };
-            Ok::<_, color_eyre::eyre::Error>(PostWithMeta { post, meta })
-        })
-        .collect::<Result<Vec<PostWithMeta>>>()?;
-
-    let post_ids = 'a: {
-        let Ok(post_ids) = fs::read_to_string(&post_ids_file) else {
-            eprintln!("Failed to read post ids file: {}", post_ids_file.display());
-            break 'a Vec::new();
-        };
-        let Ok(post_ids) = serde_json::from_str::<Vec<String>>(post_ids.as_str()) else {
-            eprintln!("Failed to parse post ids file: {}", post_ids_file.display());
-            break 'a Vec::new();
-        };
-        post_ids
-    };
-    posts.sort_unstable_by_key(|post| post_ids.iter().position(|id| id == &post.post.id));
-
-    let elapsed = t0.elapsed();
-    let total_bytes = total_bytes.into_inner();
-    println!(
-        "Processing {} posts done in : {:.2?} ({:.2} post/s), Total bytes: {} ({}/s)",
-        posts.len(),
-        elapsed,
-        posts.len() as f64 / elapsed.as_secs_f64(),
-        HumanBytes(total_bytes),
-        HumanBytes((1_000_000_000 * total_bytes as u128 / elapsed.as_nanos()) as u64)
-    );
-
-    // Write JSON
-    let t0 = Instant::now();
-    let mut posts_writer = BufWriter::new(File::create(output_file)?);
-    serde_json::to_writer(&mut posts_writer, &posts)?;
-    posts_writer.flush()?;
-    println!("Writing JSON done in : {:.2?}", t0.elapsed());
-
-    Ok(())
-}
-
-#[derive(Debug, serde::Serialize)]
-struct PostWithMeta {
-    meta: Meta,
-    #[serde(flatten)]
-    post: Post,
-}
-
-#[derive(Debug, serde::Serialize)]
-struct Meta {
-    source_created_at: Option<jiff::Timestamp>,
-    source_modified_at: Option<jiff::Timestamp>,
-    processed_at: jiff::Timestamp,
-    process_time: jiff::SignedDuration,
-}
-
-#[derive(Debug, serde::Serialize)]
-struct Post {
-    id: String,
-    #[serde(flatten)]
-    main: Main,
-    comments: Option<Vec<CommentThread>>,
-    total_comment: Option<u32>,
-}
-
-#[derive(Debug, serde::Serialize)]
-struct Main {
-    author: String,
-    publish_time: String,
-    sponsor_only: Option<String>,
-
-    content: String,
-    content_attachment: Option<ContentAttachment>,
-    poll_attachment: Option<PollAttachment>,
-    like: u32,
-}
-
-#[derive(Debug, serde::Serialize)]
-struct ContentAttachment {
-    images: Vec<String>,
-    videos: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    unknown: Vec<String>,
-}
-
-#[derive(Debug, serde::Serialize)]
-struct PollAttachment {
-    total_votes: u32,
-    items: Vec<PollItem>,
-}
-
-#[derive(Debug, serde::Serialize)]
-struct PollItem {
-    text: String,
-    percentage: String,
-}
-
-#[derive(Debug, PartialEq, serde::Serialize)]
-struct CommentThread {
-    #[serde(flatten)]
-    comment: Comment,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    replies: Vec<CommentThread>,
-}
-
-#[derive(Debug, PartialEq, serde::Serialize)]
-struct Comment {
-    author: String,
-    content: String,
-    publish_time: String,
-    url: String,
-    sponsor_duration: Option<String>,
-    sponsor_badge: Option<String>,
-    like: u32,
-}
-
-fn parse(content: &str, id: String) -> Result<Post> {
-    let html = scraper::Html::parse_document(content);
-
-    let selector = Selector::parse("body>#contents>*").unwrap();
-    let mut content_items = html.select(&selector);
-    let post = content_items.next().unwrap();
-    let comment = content_items.next();
-    assert!(content_items.next().is_none());
-    match comment {
-        Some(comment) => assert_eq!(comment.value().name(), "ytd-comments"),
-        None => assert!(
-            // ensure missing comments are due to comments being turned off
-            post.html()
-                .contains("support.google.com/youtube/answer/9706180")
-        ),
-    }
-
-    let main = post
-        .select(&Selector::parse("#post>*>#body #main").unwrap())
-        .exactly_one()
-        .unwrap();
-    let main = parse_main(main)?;
-
-    let (total_comment, comments) = if let Some(comment) = comment {
-        let (total_comment, comments) = parse_comments(comment)?;
-        (Some(total_comment), Some(comments))
-    } else {
-        (None, None)
-    };
-
-    Ok(Post {
-        id,
-        main,
-        comments,
-        total_comment,
-    })
-}
-
-fn parse_main(main: scraper::ElementRef<'_>) -> Result<Main> {
-    let author_text = main
-        .select(&Selector::parse("#author-text").unwrap())
-        .exactly_one()
-        .unwrap()
-        .text()
-        .map(|s| s.trim())
-        .collect::<String>();
-    let publish_time = main
-        .select(&Selector::parse("#published-time-text").unwrap())
-        .exactly_one()
-        .unwrap()
-        .text()
-        .collect::<String>();
-    let sponsor_only = main
-        .select(&Selector::parse("#sponsors-only-badge").unwrap())
-        .at_most_one()
-        .unwrap()
-        .map(|s| {
-            s.text()
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .join("\n")
-        })
-        .filter(|s| !s.is_empty());
-    let content = main
-        .select(&Selector::parse("#content").unwrap())
-        .next()
-        .unwrap()
-        .text()
-        .map(|s| s.trim())
-        .collect::<String>();
-    let content_attachment = main
-        .select(&Selector::parse("#content-attachment:not([hidden])").unwrap())
-        .next()
-        .map(|c| {
-            let images = c
-                .select(&Selector::parse("img[src]").unwrap())
-                .map(|img| img.attr("src").unwrap().to_owned())
-                .collect::<Vec<_>>();
-            let (videos, unknown) = c
-                .select(&Selector::parse("a[href]").unwrap())
-                .map(|link| link.attr("href").unwrap().to_owned())
-                .filter(|link| {
-                    // filter out self-link to current channel
-                    link.contains("/@").not()
-                })
-                .dedup()
-                .partition(|link| link.contains("/watch?v="));
-
-            ContentAttachment {
-                images,
-                videos,
-                unknown,
-            }
-        });
-    let poll_attachment = main
-        .select(&Selector::parse("#poll-attachment:not([hidden])").unwrap())
-        .at_most_one()
-        .unwrap()
-        .map(|poll| -> Result<PollAttachment> {
-            let total_votes = poll
-                .select(&Selector::parse("#vote-info").unwrap())
-                .exactly_one()
-                .unwrap()
-                .text()
-                .collect::<String>();
-            let total_votes =
-                parse_vote(total_votes.trim_end_matches("คะแนน")).wrap_err_with(|| {
-                    format!("total votes should be a parseable number: {total_votes}")
-                })?;
-
-            let items = poll
-                .select(&Selector::parse("a[role='option'] .choice-info").unwrap())
-                .map(|poll| {
-                    let text = poll
-                        .select(&Selector::parse(".choice-text").unwrap())
-                        .exactly_one()
-                        .unwrap()
-                        .text()
-                        .collect::<String>();
-                    let percentage = poll
-                        .select(&Selector::parse(".vote-percentage").unwrap())
-                        .exactly_one()
-                        .unwrap()
-                        .text()
-                        .collect::<String>();
-                    PollItem { text, percentage }
-                })
-                .collect();
-
-            Ok(PollAttachment { total_votes, items })
-        })
-        .transpose()?;
-
-    let like = main
-        .select(&Selector::parse("#vote-count-middle").unwrap())
-        .next()
-        .unwrap()
-        .text()
-        .collect::<String>();
-    let like = parse_vote(&like)?;
-
-    Ok(Main {
-        author: author_text,
-        publish_time,
-        sponsor_only,
-        content,
-        content_attachment,
-        poll_attachment,
-        like,
-    })
-}
-
-fn parse_comments(comment: scraper::ElementRef<'_>) -> Result<(u32, Vec<CommentThread>)> {
-    // TODO: used to validate
-    let n: u32 = comment
-        .select(&Selector::parse("#count").unwrap())
-        .exactly_one()
-        .map_err(|err| eyre!("{}", err))
-        .wrap_err("comments should has exactly one count")?
-        .text()
-        .find_map(|t| parse_numerical_int(t).ok())
-        .wrap_err("comments' count should be a number")?;
-
-    let s = Selector::parse("#contents>ytd-comment-thread-renderer:not([is-sub-thread])").unwrap();
-    let threads = comment.select(&s);
-    let comments = threads
-        .enumerate()
-        .map(|(i, thread)| {
-            parse_comment_thread(thread)
-                .wrap_err_with(|| format!("fail to parse comment thread [{}]", i))
-                .with_section(|| {
-                    thread
-                        .text()
-                        .map(|s| s.trim())
-                        .collect::<String>()
-                        .header("Comment thread's text")
-                })
-        })
-        .collect::<Result<Vec<_>>>()?;
-
-    // cannot compare exactly because youtube may hide some comments
-    // ensure!(
-    //     n >= comments.len() as u32,
-    //     "provided comment count ({}) should be at least the number of visible comments ({})",
-    //     n,
-    //     comments.len()
-    // );
-
-    Ok((n, comments))
-}
-
-fn parse_comment_thread(thread: scraper::ElementRef<'_>) -> Result<CommentThread> {
-    struct CommentTree<'a> {
-        comment: ElementRef<'a>,
-        children: Vec<CommentTree<'a>>,
-    }
-
-    fn build_comment_trees(thread: ElementRef<'_>) -> Result<CommentTree<'_>> {
-        ensure!(thread.value().name() == "ytd-comment-thread-renderer");
-
-        // Find direct comment, not inside replies (relative to current element)
-        let comment = thread
-            .select(&Selector::parse("#comment:not(:scope #replies *)").unwrap())
-            .exactly_one()
-            .unwrap();
-
-        // Find direct children threads, exactly one level inside replies.
-        // Build comment tree from them.
-        let children = thread
-            .select(
-                &Selector::parse(
-                    "#replies:not([hidden]) ytd-comment-thread-renderer:not(:scope #replies #replies *)",
-                )
-                .unwrap(),
-            )
-            .map(|c| build_comment_trees(c))
-            .collect::<Result<Vec<_>>>()?;
-
-        Ok(CommentTree { comment, children })
-    }
-
-    let tree = build_comment_trees(thread)?;
-
-    let comment = parse_comment(tree.comment)?;
-
-    // If the tree has only one node then it's either from before youtube thread comment or have no replies.
-    // Parsing with pre thread comment method should handle both case
-    if tree.children.is_empty() {
-        let replies = thread
-            .select(&Selector::parse("#replies:not([hidden]) #contents>*").unwrap())
-            .map(|e| {
-                Ok(CommentThread {
-                    comment: parse_comment(e)?,
-                    replies: Vec::new(),
-                })
-            })
-            .collect::<Result<_>>()?;
-        return Ok(CommentThread { comment, replies });
-    }
-
-    fn build_replies(tree: &CommentTree<'_>) -> Result<CommentThread> {
-        let comment = parse_comment(tree.comment)?;
-        let replies = tree
-            .children
-            .iter()
-            .enumerate()
-            .map(|(i, c)| {
-                build_replies(c).wrap_err_with(|| format!("failed to build reply [{}]", i))
-            })
-            .collect::<Result<Vec<_>>>()?;
-        Ok(CommentThread { comment, replies })
-    }
-
-    build_replies(&tree)
-}
-
-fn parse_comment(comment: scraper::ElementRef<'_>) -> Result<Comment> {
-    let author = comment
-        .select(&Selector::parse("#author-text").unwrap())
-        .exactly_one()
-        .map_err(|err| {
-            let msg = err.to_string();
-            let authors = err.map(|x| x.text().collect::<String>()).collect_vec();
-            eyre!("{} {:?}", msg, authors)
-        })
-        .wrap_err("comment should has exactly one author")?
-        .text()
-        .map(|s| s.trim())
-        .collect::<String>();
-
-    let publish_time = comment
-        .select(&Selector::parse("#published-time-text").unwrap())
-        .exactly_one()
-        .map_err(|err| eyre!("{}", err))
-        .wrap_err("comment should has exactly one publish time")?
-        .text()
-        .map(|s| s.trim())
-        .collect::<String>();
-
-    let url = comment
-        .select(&Selector::parse("#published-time-text a[href]").unwrap())
-        .exactly_one()
-        .map_err(|err| eyre!("{}", err))
-        .wrap_err("comment should has exactly one publish time")?
-        .attr("href")
-        .expect("selector specified href attribute")
-        .to_owned();
-
-    let sponsor = comment
-        .select(
-            &Selector::parse("#sponsor-comment-badge>ytd-sponsor-comment-badge-renderer").unwrap(),
-        )
-        .at_most_one()
-        .map_err(|err| eyre!("{}", err))
-        .wrap_err("comment should has at most one sponsor duration")?;
-    let sponsor_duration = sponsor
-        .map(|ele| -> Result<String> {
-            Ok(ele
-                .attr("aria-label")
-                .wrap_err("comment's sponsor's aria-label should exists for sponsor duration")?
-                .to_owned())
-        })
-        .transpose()?;
-    let sponsor_badge = sponsor
-        .map(|ele| -> Result<Option<String>> {
-            Ok(ele
-                .select(&Selector::parse("img[src]").unwrap())
-                .at_most_one()
-                .map_err(|err| eyre!("{}", err))
-                .wrap_err("comment's sponsor's badge should not be more than one")?
-                .map(|ele| ele.attr("src").unwrap().to_owned()))
-        })
-        .transpose()?
-        .flatten();
-
-    let like = comment
-        .select(&Selector::parse("#vote-count-middle").unwrap())
-        .next()
-        .wrap_err("comment should has at least one like")?
-        .text()
-        .collect::<String>();
-    let like = parse_vote(&like)?;
-
-    let content = comment
-        .select(&Selector::parse("#content-text>*").unwrap())
-        .exactly_one()
-        .map_err(|err| eyre!("{}", err))
-        .wrap_err("comment should has exactly one content")?;
-    let content = content
-        .children()
-        .map(stringify_content_item)
-        .collect::<Result<String>>()?
-        .trim()
-        .to_owned();
-
-    Ok(Comment {
-        author,
-        publish_time,
-        url,
-        sponsor_duration,
-        sponsor_badge,
-        like,
-        content,
-    })
-}
-
-fn stringify_content_item(item: ego_tree::NodeRef<Node>) -> Result<String> {
-    // Span
-    if let Some(e) = ElementRef::wrap(item) {
-        if e.value().name() != "span" {
-            panic!("unexpected element: {:?}", e.html());
-        }
-
-        if let Some(child) = e.first_element_child() {
-            match child.value().name() {
-                // Likly a emoji
-                "img" => {
-                    let src = child
-                        .attr("src")
-                        .ok_or(eyre!("img should have src: {}", child.html()))?;
-                    let alt = child.attr("alt");
-                    match EMOTE_RESOLVER.get().unwrap().resolve_emoji(src, alt) {
-                        Some(res) => return Ok(res),
-                        None => {
-                            eprintln!("failed to resolve emoji: {}", src);
-                            return Ok(format!("<img src=\"{}\">", src));
-                        }
-                    }
-                }
-                // Some kind of link
-                "a" => {
-                    let href = child.attr("href").unwrap();
-                    let text = child.text().collect::<String>();
-
-                    // push whitespace out
-                    let wi = text.len() - text.trim_start().len();
-                    let wj = text.len() - text.trim_end().len();
-                    let (text, wr) = text.split_at(text.len() - wj);
-                    let (wl, text) = text.split_at(wi);
-
-                    return Ok(format!("{}<a href=\"{}\">{}</a>{}", wl, href, text, wr));
-                }
-                // Nested
-                "span" => {
-                    return e
-                        .children()
-                        .map(stringify_content_item)
-                        .collect::<Result<String>>();
-                }
-                _ => {
-                    panic!("unhandled span's child element: {}", child.html());
-                }
-            }
-        }
-
-        // Likely a styled text
-        let text = e.text().collect::<String>();
-        let text = text.trim();
-
-        if !text.is_empty() {
-            if e.attr("style")
-                .is_some_and(|style| style.contains("font-weight: 500"))
-            {
-                return Ok(format!("<b>{}</b>", text));
-            } else {
-                return Ok(text.to_string());
-            }
-        }
-
-        panic!("unhandled span: {:?}", e.html());
-    };
-
-    // Plain text
-    if let Some(t) = item.value().as_text() {
-        return Ok(t.trim().to_owned());
-    }
-
-    panic!("unexpected node: {:?}", item);
-}
-
-fn parse_vote(vote: &str) -> Result<u32> {
-    let vote = vote.trim();
-    if vote.is_empty() {
-        return Ok(0);
-    }
-    parse_numerical_int(vote)
-}
-
-fn parse_numerical_int(s: &str) -> Result<u32> {
-    let s = s.trim().replace([',', ' '], "");
-    if let Some(s) = s.strip_suffix("พัน") {
-        return Ok((s.parse::<f64>()? * 1_000.0) as u32);
-    }
-    if let Some(s) = s.strip_suffix("หมื่น") {
-        return Ok((s.parse::<f64>()? * 10_000.0) as u32);
-    }
-    if let Some(s) = s.strip_suffix("แสน") {
-        return Ok((s.parse::<f64>()? * 100_000.0) as u32);
-    }
-    Ok(s.parse::<u32>()?)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    mod content_item {
-        use super::*;
-        use pretty_assertions::assert_str_eq;
-
-        #[test]
-        fn test_stingify() {
-            let html = r#"<span class="yt-core-attributed-string yt-core-attributed-string--white-space-pre-wrap" dir="auto"><span class="" dir="auto" style="font-weight: 400;">
-<a class="yt-core-attributed-string__link yt-core-attributed-string__link--call-to-action-color" tabindex="0" href="/channel/UCIRjccPaf9aWkNu5cxXJEUg" target="" force-new-state="true">&nbsp;@괜찮아이츠키&nbsp;</a></span>どーせ転生すんだろ笑笑すぐ会えるよー笑笑無意味過ぎる</span>"#;
-            let html = scraper::Html::parse_fragment(html);
-            let content_item = stringify_content_item(
-                *html
-                    .root_element()
-                    .first_element_child()
-                    .expect("top element is <html> and its first child is our input fragment"),
-            )
-            .unwrap();
-            assert_str_eq!(
-                content_item.trim(),
-                "<a href=\"/channel/UCIRjccPaf9aWkNu5cxXJEUg\">@괜찮아이츠키</a> どーせ転生すんだろ笑笑すぐ会えるよー笑笑無意味過ぎる"
-            );
-        }
-    }
-
-    mod comment_thread {
-        use insta::assert_json_snapshot;
-        use std::collections::HashMap;
-
-        use super::*;
-
-        fn parse_thread_from_fixture(fixture_name: &str) -> Result<CommentThread> {
-            // Initialize emote resolver with empty mapping for tests
-            EMOTE_RESOLVER
-                .set(EmoteResolver::with_mapping(HashMap::default()))
-                .ok();
-
-            // Load HTML fixture
-            let fixture_path = format!(
-                "{}/tests/fixtures/{}",
-                env!("CARGO_MANIFEST_DIR"),
-                fixture_name
-            );
-            let html = std::fs::read_to_string(&fixture_path).expect("fixture file should exist");
-
-            // Parse HTML and extract comment thread
-            let html = scraper::Html::parse_fragment(&html);
-            parse_comment_thread(
-                html.root_element()
-                    .first_element_child()
-                    .expect("top element is <html> and its first child is our input fragment"),
-            )
-        }
-
-        #[test]
-        fn test_threaded_replied() {
-            let thread = parse_thread_from_fixture("threaded_replied.html").unwrap();
-
-            assert_json_snapshot!(thread, @r#"
-            {
-              "author": "@龍-x6w7c",
-              "content": "今日は本当にいいライブだったよ!!6年間本当にありがとう😊",
-              "publish_time": "20 ชั่วโมงที่ผ่านมา",
-              "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg",
-              "sponsor_duration": null,
-              "sponsor_badge": null,
-              "like": 300,
-              "replies": [
-                {
-                  "author": "@Wolfs97",
-                  "content": "悲しい",
-                  "publish_time": "16 ชั่วโมงที่ผ่านมา",
-                  "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARF8_UOHFLV",
-                  "sponsor_duration": null,
-                  "sponsor_badge": null,
-                  "like": 7
-                },
-                {
-                  "author": "@LolXD-rl7gg",
-                  "content": "Amaneeee😭😭😭😭💙💙💙💙thank you for past 6 years so😭😭💙💙💙",
-                  "publish_time": "18 ชั่วโมงที่ผ่านมา",
-                  "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8AREwUJ0hDAD",
-                  "sponsor_duration": null,
-                  "sponsor_badge": null,
-                  "like": 5
-                },
-                {
-                  "author": "@괜찮아이츠키",
-                  "content": "今まで本当にありがとう😢ゆっくり休んでね。また何処かで会えたらな。",
-                  "publish_time": "9 ชั่วโมงที่ผ่านมา",
-                  "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARFsyJznk92",
-                  "sponsor_duration": "สมาชิกใหม่",
-                  "sponsor_badge": "https://yt3.ggpht.com/l3iBRUbum0N6BsR7SJNKvR4ZlVox7gVDDGKClQQ4fBHBm-tuxM7nkhJacX49DkIfo55YYUyL9C0=s32-k-nd",
-                  "like": 5,
-                  "replies": [
-                    {
-                      "author": "@いをと",
-                      "content": "<a href=\"/channel/UCIRjccPaf9aWkNu5cxXJEUg\">@괜찮아이츠키</a> どーせ転生すんだろ笑笑すぐ会えるよー笑笑無意味過ぎる",
-                      "publish_time": "8 นาทีที่ผ่านมา",
-                      "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARGsW6iq7S5",
-                      "sponsor_duration": null,
-                      "sponsor_badge": null,
-                      "like": 0
-                    }
-                  ]
-                }
-              ]
-            }
-            "#);
-        }
-
-        #[test]
-        fn test_pre_thread_replied() -> Result<()> {
-            let thread = parse_thread_from_fixture("pre_thread_replied.html")?;
-
-            assert_json_snapshot!(thread, @r#"
-            {
-              "author": "@Redacted",
-              "content": "Eh? Both links are in Japanese.",
-              "publish_time": "1 ปีที่แล้ว",
-              "url": "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
-              "sponsor_duration": null,
-              "sponsor_badge": null,
-              "like": 4,
-              "replies": [
-                {
-                  "author": "@Redacted",
-                  "content": "One says it's the Japan dubbed version and the other is the original English voices",
-                  "publish_time": "1 ปีที่แล้ว",
-                  "url": "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMOR-vpM9U&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
-                  "sponsor_duration": null,
-                  "sponsor_badge": null,
-                  "like": 2
-                },
-                {
-                  "author": "@Redacted",
-                  "content": "2nd link has English audio",
-                  "publish_time": "1 ปีที่แล้ว",
-                  "url": "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMjoxWYh6a&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
-                  "sponsor_duration": null,
-                  "sponsor_badge": null,
-                  "like": 3
-                }
-              ]
-            }
-            "#);
-
-            Ok(())
-        }
-    }
+    let Cli { common, command } = Cli::parse();
+    match command {
+        Command::Process(args) => cmd::process::run(&common, args),
+        Command::Check(args) => cmd::check::run(&common, args),
+        Command::IdsCheck(args) => return cmd::ids_check::run(&common, args),
+        Command::Images(args) => cmd::images::run(&common, args),
+        Command::Emotes(command) => cmd::emotes::run(&common, command),
+        Command::Export(command) => cmd::export::run(&common, command),
+        Command::Stats(args) => cmd::stats::run(&common, args),
+        Command::Migrate(args) => cmd::migrate::run(&common, args),
+        Command::Schema => cmd::schema::run(),
+    }?;
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,110 @@
+use crate::locale::Locale;
+
+/// Members-only badge of a post.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct SponsorOnly {
+    /// Badge text as displayed, lines joined by `\n`.
+    pub text: String,
+    /// Membership tier the post is limited to, `None` if the badge does not name one.
+    pub tier: Option<String>,
+}
+
+impl SponsorOnly {
+    /// Parse badge `text`, the tier is whatever surrounds the locale's members-only label.
+    /// Text without a known label is kept as is without a tier.
+    pub fn parse(text: String, locale: Locale) -> Self {
+        let tier = locale
+            .strip_members_only_label(&text)
+            .map(|rest| {
+                rest.trim_matches(|c: char| c.is_whitespace() || "·•|:：-–()（）".contains(c))
+                    .to_owned()
+            })
+            .filter(|tier| !tier.is_empty());
+        Self { text, tier }
+    }
+}
+
+/// Membership of a commenter, from the sponsor badge next to their name.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct SponsorDuration {
+    /// Badge label as displayed, ie. `สมาชิกมา 2 ปี`.
+    pub text: String,
+    /// How long they have been a member, `0` for new members and `None` if the label cannot be parsed.
+    pub months: Option<u32>,
+    /// Whether they joined less than a month ago.
+    pub new_member: bool,
+}
+
+impl SponsorDuration {
+    pub fn parse(text: String, locale: Locale) -> Self {
+        let new_member = locale.is_new_member(&text);
+        let months = if new_member {
+            Some(0)
+        } else {
+            locale.parse_membership_months(&text)
+        };
+        Self {
+            text,
+            months,
+            new_member,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sponsor_only() {
+        let cases = [
+            (Locale::Th, "สมาชิกเท่านั้น", None),
+            (Locale::Th, "เฉพาะสมาชิก\nMel's Fans", Some("Mel's Fans")),
+            (Locale::En, "Members only", None),
+            (Locale::En, "members only (Night Sky)", Some("Night Sky")),
+            (Locale::Ja, "メンバー限定（ヨゾラ）", Some("ヨゾラ")),
+            (Locale::Ko, "회원 전용 · 별빛", Some("별빛")),
+            (Locale::En, "Supporters", None),
+        ];
+        for (locale, text, tier) in cases {
+            let badge = SponsorOnly::parse(text.to_owned(), locale);
+            assert_eq!(badge.text, text);
+            assert_eq!(badge.tier.as_deref(), tier, "{locale:?} {text:?}");
+        }
+    }
+
+    #[test]
+    fn test_sponsor_duration() {
+        let cases = [
+            (Locale::Th, "สมาชิกใหม่", Some(0), true),
+            (Locale::Th, "สมาชิกมา 2 ปี", Some(24), false),
+            (Locale::Th, "สมาชิกมา 1 ปี 3 เดือน", Some(15), false),
+            (Locale::Th, "สมาชิกมา 6 เดือน", Some(6), false),
+            (Locale::En, "New member", Some(0), true),
+            (Locale::En, "Member for 1 year, 2 months", Some(14), false),
+            (Locale::En, "Member (6 months)", Some(6), false),
+            (Locale::Ja, "新規メンバー", Some(0), true),
+            (Locale::Ja, "メンバー（1 年 2 か月）", Some(14), false),
+            (Locale::Ko, "신규 회원", Some(0), true),
+            (Locale::Ko, "회원 가입 기간: 2년", Some(24), false),
+            (Locale::Ko, "3개월", Some(3), false),
+            (Locale::En, "Member", None, false),
+            (Locale::En, "สมาชิกมา 2 ปี", None, false),
+            (Locale::En, "Member for 999999999 years", None, false),
+            (
+                Locale::En,
+                "Member for 357913941 years, 4 months",
+                None,
+                false,
+            ),
+        ];
+        for (locale, text, months, new_member) in cases {
+            let duration = SponsorDuration::parse(text.to_owned(), locale);
+            assert_eq!(
+                (duration.months, duration.new_member),
+                (months, new_member),
+                "{locale:?} {text:?}"
+            );
+        }
+    }
+}
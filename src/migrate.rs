@@ -0,0 +1,644 @@
+use color_eyre::{
+    Result,
+    eyre::{Context, bail, ensure},
+};
+use itertools::Itertools;
+use scraper::Node;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    link::{CommentUrl, LinkTarget},
+    locale::Locale,
+    membership::{SponsorDuration, SponsorOnly},
+    model::{Archive, AuthorChannel, SCHEMA_VERSION},
+    parse::{estimate_votes, percentage_value},
+    publish_time::PublishTime,
+    rich_text::{RichText, Span, Style},
+};
+
+/// Schema version of a posts.json, bare arrays of posts predate the envelope and are version 1.
+pub fn schema_version(value: &Value) -> Result<u32> {
+    match value {
+        Value::Array(_) => Ok(1),
+        Value::Object(object) => match object.get("schema_version").and_then(Value::as_u64) {
+            Some(version) => Ok(version.try_into()?),
+            None => bail!("missing schema_version"),
+        },
+        _ => bail!("expected an array of posts or an object"),
+    }
+}
+
+/// Upgrade a posts.json of any earlier schema version to [`SCHEMA_VERSION`],
+/// returning the version it was upgraded from.
+pub fn migrate(mut value: Value) -> Result<(u32, Archive)> {
+    let from = schema_version(&value)?;
+    ensure!(
+        from <= SCHEMA_VERSION,
+        "schema version {from} is newer than the supported version {SCHEMA_VERSION}"
+    );
+
+    for version in from..SCHEMA_VERSION {
+        value = match version {
+            1 => v1_to_v2(value),
+            2 => v2_to_v3(value),
+            3 => v3_to_v4(value),
+            4 => v4_to_v5(value),
+            5 => v5_to_v6(value),
+            _ => unreachable!("missing migration from schema version {version}"),
+        }
+        .wrap_err_with(|| format!("fail to migrate from schema version {version}"))?;
+    }
+
+    let archive = serde_json::from_value(value).wrap_err("fail to parse migrated posts")?;
+    Ok((from, archive))
+}
+
+/// Wrap the bare array of posts into the envelope, converting fields of older parser outputs:
+/// - `content` string (with `<a>`, `<b>` and unresolved `<img>`) into spans
+/// - `publish_time` string into a [`PublishTime`], resolved against the source modified time
+/// - `content_attachment.videos`/`unknown` and link strings into typed links
+fn v1_to_v2(value: Value) -> Result<Value> {
+    let Value::Array(mut posts) = value else {
+        bail!("expected an array of posts");
+    };
+
+    for (i, post) in posts.iter_mut().enumerate() {
+        let post = post
+            .as_object_mut()
+            .ok_or_else(|| color_eyre::eyre::eyre!("post [{i}] is not an object"))?;
+        let captured_at = post
+            .get("meta")
+            .and_then(|meta| meta.get("source_modified_at"))
+            .and_then(Value::as_str)
+            .and_then(|t| t.parse().ok());
+
+        upgrade_content(post, captured_at)?;
+        if let Some(attachment) = post
+            .get_mut("content_attachment")
+            .and_then(Value::as_object_mut)
+        {
+            upgrade_attachment(attachment);
+        }
+        if let Some(poll) = post
+            .get_mut("poll_attachment")
+            .and_then(Value::as_object_mut)
+        {
+            upgrade_poll(poll);
+        }
+        if let Some(Value::Array(comments)) = post.get_mut("comments") {
+            upgrade_threads(comments, captured_at)?;
+        }
+    }
+
+    let channel = posts
+        .iter()
+        .filter_map(|post| post.get("author")?.as_str())
+        .counts()
+        .into_iter()
+        .max_by_key(|&(name, count)| (count, std::cmp::Reverse(name)))
+        .map(|(name, _)| json!({ "name": name }));
+    let generated_at = posts
+        .iter()
+        .filter_map(|post| {
+            post.get("meta")?
+                .get("processed_at")?
+                .as_str()?
+                .parse::<jiff::Timestamp>()
+                .ok()
+        })
+        .max()
+        .unwrap_or_else(jiff::Timestamp::now);
+
+    Ok(json!({
+        "schema_version": 2,
+        "parser_version": null,
+        "channel": channel,
+        "generated_at": generated_at,
+        "posts": posts,
+    }))
+}
+
+/// Parse `sponsor_only` and comments' `sponsor_duration` strings into typed memberships.
+/// The locale of older outputs is not recorded, so each text is parsed with the first locale recognizing it.
+fn v2_to_v3(mut value: Value) -> Result<Value> {
+    fn upgrade_threads(threads: &mut [Value]) -> Result<()> {
+        for thread in threads {
+            let Some(thread) = thread.as_object_mut() else {
+                continue;
+            };
+            if let Some(Value::String(text)) = thread.get("sponsor_duration") {
+                let locale = Locale::ALL
+                    .into_iter()
+                    .find(|locale| {
+                        locale.is_new_member(text) || locale.parse_membership_months(text).is_some()
+                    })
+                    .unwrap_or_default();
+                let duration = SponsorDuration::parse(text.clone(), locale);
+                thread.insert(
+                    "sponsor_duration".to_owned(),
+                    serde_json::to_value(duration)?,
+                );
+            }
+            if let Some(Value::Array(replies)) = thread.get_mut("replies") {
+                upgrade_threads(replies)?;
+            }
+        }
+        Ok(())
+    }
+
+    let Some(Value::Array(posts)) = value.get_mut("posts") else {
+        bail!("expected posts");
+    };
+    for post in posts.iter_mut().filter_map(Value::as_object_mut) {
+        if let Some(Value::String(text)) = post.get("sponsor_only") {
+            let locale = Locale::ALL
+                .into_iter()
+                .find(|locale| locale.strip_members_only_label(text).is_some())
+                .unwrap_or_default();
+            let sponsor_only = SponsorOnly::parse(text.clone(), locale);
+            post.insert(
+                "sponsor_only".to_owned(),
+                serde_json::to_value(sponsor_only)?,
+            );
+        }
+        if let Some(Value::Array(comments)) = post.get_mut("comments") {
+            upgrade_threads(comments)?;
+        }
+    }
+    value["schema_version"] = 3.into();
+    Ok(value)
+}
+
+/// Mark comments edited from their publish time text, other comment signals (hearted, pinned, ...)
+/// were not parsed before and are left unset.
+fn v3_to_v4(mut value: Value) -> Result<Value> {
+    fn upgrade_threads(threads: &mut [Value]) {
+        for thread in threads.iter_mut().filter_map(Value::as_object_mut) {
+            let edited = thread
+                .get("publish_time")
+                .and_then(|time| time.get("text"))
+                .and_then(Value::as_str)
+                .is_some_and(|text| Locale::ALL.iter().any(|locale| locale.is_edited(text)));
+            if edited {
+                thread.insert("edited".to_owned(), true.into());
+            }
+            if let Some(Value::Array(replies)) = thread.get_mut("replies") {
+                upgrade_threads(replies);
+            }
+        }
+    }
+
+    let Some(Value::Array(posts)) = value.get_mut("posts") else {
+        bail!("expected posts");
+    };
+    for post in posts.iter_mut() {
+        if let Some(Value::Array(comments)) = post.get_mut("comments") {
+            upgrade_threads(comments);
+        }
+    }
+    value["schema_version"] = 4.into();
+    Ok(value)
+}
+
+/// Add `author_channel` to posts and comments, the links and avatars were not parsed before
+/// so only the handle of comment authors displayed as `@handle` is known.
+fn v4_to_v5(mut value: Value) -> Result<Value> {
+    fn upgrade_author(item: &mut Map<String, Value>) -> Result<()> {
+        let name = item
+            .get("author")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let channel = AuthorChannel::new(None, name, None);
+        item.insert("author_channel".to_owned(), serde_json::to_value(channel)?);
+        Ok(())
+    }
+
+    fn upgrade_threads(threads: &mut [Value]) -> Result<()> {
+        for thread in threads.iter_mut().filter_map(Value::as_object_mut) {
+            upgrade_author(thread)?;
+            if let Some(Value::Array(replies)) = thread.get_mut("replies") {
+                upgrade_threads(replies)?;
+            }
+        }
+        Ok(())
+    }
+
+    let Some(Value::Array(posts)) = value.get_mut("posts") else {
+        bail!("expected posts");
+    };
+    for post in posts.iter_mut().filter_map(Value::as_object_mut) {
+        upgrade_author(post)?;
+        if let Some(Value::Array(comments)) = post.get_mut("comments") {
+            upgrade_threads(comments)?;
+        }
+    }
+    value["schema_version"] = 5.into();
+    Ok(value)
+}
+
+/// Decode comments' `comment_id`, `parent_comment_id` and `post_id` from their url.
+fn v5_to_v6(mut value: Value) -> Result<Value> {
+    fn upgrade_threads(threads: &mut [Value]) {
+        for thread in threads.iter_mut().filter_map(Value::as_object_mut) {
+            let ids = thread
+                .get("url")
+                .and_then(Value::as_str)
+                .and_then(CommentUrl::parse);
+            let (comment_id, parent_comment_id, post_id) = match ids {
+                Some(ids) => (
+                    Some(ids.comment_id),
+                    ids.parent_comment_id,
+                    Some(ids.post_id),
+                ),
+                None => (None, None, None),
+            };
+            thread.insert("comment_id".to_owned(), comment_id.into());
+            thread.insert("parent_comment_id".to_owned(), parent_comment_id.into());
+            thread.insert("post_id".to_owned(), post_id.into());
+            if let Some(Value::Array(replies)) = thread.get_mut("replies") {
+                upgrade_threads(replies);
+            }
+        }
+    }
+
+    let Some(Value::Array(posts)) = value.get_mut("posts") else {
+        bail!("expected posts");
+    };
+    for post in posts.iter_mut() {
+        if let Some(Value::Array(comments)) = post.get_mut("comments") {
+            upgrade_threads(comments);
+        }
+    }
+    value["schema_version"] = 6.into();
+    Ok(value)
+}
+
+fn upgrade_threads(threads: &mut [Value], captured_at: Option<jiff::Timestamp>) -> Result<()> {
+    for thread in threads {
+        let Some(thread) = thread.as_object_mut() else {
+            continue;
+        };
+        upgrade_content(thread, captured_at)?;
+        if let Some(Value::Array(replies)) = thread.get_mut("replies") {
+            upgrade_threads(replies, captured_at)?;
+        }
+    }
+    Ok(())
+}
+
+/// Upgrade `content` and `publish_time` of a post or comment.
+fn upgrade_content(
+    item: &mut Map<String, Value>,
+    captured_at: Option<jiff::Timestamp>,
+) -> Result<()> {
+    if let Some(Value::String(content)) = item.get("content") {
+        let content = legacy_rich_text(content);
+        item.insert("content".to_owned(), serde_json::to_value(content)?);
+    }
+    if let Some(Value::String(text)) = item.get("publish_time") {
+        // older parser only supported Thai
+        let publish_time = PublishTime::resolve(text.clone(), Locale::Th, captured_at);
+        item.insert(
+            "publish_time".to_owned(),
+            serde_json::to_value(publish_time)?,
+        );
+    }
+    Ok(())
+}
+
+fn upgrade_attachment(attachment: &mut Map<String, Value>) {
+    let links = ["links", "videos", "unknown"]
+        .into_iter()
+        .filter_map(|key| match attachment.remove(key) {
+            Some(Value::Array(links)) => Some(links),
+            _ => None,
+        })
+        .flatten()
+        .map(|link| match link {
+            Value::String(url) => json!({ "url": url, "target": LinkTarget::classify(&url) }),
+            link => link,
+        })
+        .collect();
+    attachment.insert("links".to_owned(), Value::Array(links));
+}
+
+/// Fill in poll options' percentage value and estimated votes from their displayed percentage,
+/// left `null` when it does not parse.
+fn upgrade_poll(poll: &mut Map<String, Value>) {
+    let total_votes = poll
+        .get("total_votes")
+        .and_then(Value::as_u64)
+        .and_then(|n| u32::try_from(n).ok());
+    let Some(Value::Array(items)) = poll.get_mut("items") else {
+        return;
+    };
+    for item in items.iter_mut().filter_map(Value::as_object_mut) {
+        let value = item
+            .get("percentage")
+            .and_then(Value::as_str)
+            .and_then(|text| percentage_value(text).ok().flatten());
+        let estimated_votes = total_votes.zip(value).map(|(n, p)| estimate_votes(n, p));
+        item.insert("percentage_value".to_owned(), json!(value));
+        item.insert("estimated_votes".to_owned(), json!(estimated_votes));
+    }
+}
+
+/// Spans of content from before rich text, ie. `<a href="/channel/UC...">@name</a> text <b>bold</b>`.
+fn legacy_rich_text(content: &str) -> RichText {
+    fn visit(node: ego_tree::NodeRef<'_, Node>, style: Style, out: &mut RichText) {
+        match node.value() {
+            Node::Text(text) => out.push_text(text, style),
+            Node::Element(e) => match e.name() {
+                "a" => {
+                    let text = node
+                        .descendants()
+                        .filter_map(|n| n.value().as_text().map(|t| &**t))
+                        .collect::<String>();
+                    out.push_link(&text, e.attr("href").unwrap_or_default(), style);
+                }
+                "img" => out.0.push(Span::Emote {
+                    name: None,
+                    url: e.attr("src").unwrap_or_default().to_owned(),
+                }),
+                "br" => out.push_text("\n", style),
+                name => {
+                    let style = Style {
+                        bold: style.bold || name == "b",
+                        ..style
+                    };
+                    for child in node.children() {
+                        visit(child, style, out);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    let html = scraper::Html::parse_fragment(content);
+    let mut text = RichText::default();
+    for child in html.root_element().children() {
+        visit(child, Style::default(), &mut text);
+    }
+    text.trim();
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v1() {
+        let v1 = json!([{
+            "meta": {
+                "source_created_at": null,
+                "source_modified_at": "2024-01-17T12:00:00Z",
+                "processed_at": "2024-01-18T00:00:00Z",
+                "process_time": "PT0.01S"
+            },
+            "id": "Ugkx",
+            "author": "Amane Kanata",
+            "publish_time": "1 วันที่ผ่านมา",
+            "sponsor_only": null,
+            "content": "hi <b>all</b> <a href=\"/hashtag/amanetime\">#amanetime</a>",
+            "content_attachment": {
+                "images": [],
+                "videos": ["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]
+            },
+            "poll_attachment": {
+                "total_votes": 10,
+                "items": [{ "text": "yes", "percentage": "60%" }]
+            },
+            "like": 5,
+            "comments": [{
+                "author": "@a",
+                "content": "<img src=\"https://yt3.ggpht.com/abc=w24-h24-c-k-nd\">",
+                "publish_time": "2 ชั่วโมงที่ผ่านมา",
+                "url": "/post/Ugkx?lc=Ugz",
+                "sponsor_duration": null,
+                "sponsor_badge": null,
+                "like": 0
+            }],
+            "total_comment": 1
+        }]);
+
+        let (from, archive) = migrate(v1).unwrap();
+        assert_eq!(from, 1);
+        insta::assert_json_snapshot!(archive, @r##"
+        {
+          "schema_version": 6,
+          "parser_version": null,
+          "channel": {
+            "name": "Amane Kanata"
+          },
+          "generated_at": "2024-01-18T00:00:00Z",
+          "posts": [
+            {
+              "meta": {
+                "source_created_at": null,
+                "source_modified_at": "2024-01-17T12:00:00Z",
+                "processed_at": "2024-01-18T00:00:00Z",
+                "process_time": "PT0.01S"
+              },
+              "id": "Ugkx",
+              "author": "Amane Kanata",
+              "author_channel": {
+                "url": null,
+                "channel_id": null,
+                "handle": null,
+                "avatar": null
+              },
+              "publish_time": {
+                "text": "1 วันที่ผ่านมา",
+                "earliest": "2024-01-15T12:00:00Z",
+                "latest": "2024-01-16T12:00:00Z"
+              },
+              "sponsor_only": null,
+              "content": [
+                {
+                  "type": "text",
+                  "text": "hi "
+                },
+                {
+                  "type": "text",
+                  "text": "all",
+                  "bold": true
+                },
+                {
+                  "type": "text",
+                  "text": " "
+                },
+                {
+                  "type": "hashtag",
+                  "text": "#amanetime",
+                  "url": "/hashtag/amanetime",
+                  "target": {
+                    "kind": "hashtag",
+                    "tag": "amanetime"
+                  }
+                }
+              ],
+              "content_attachment": {
+                "images": [],
+                "links": [
+                  {
+                    "url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                    "target": {
+                      "kind": "video",
+                      "id": "dQw4w9WgXcQ"
+                    }
+                  }
+                ]
+              },
+              "poll_attachment": {
+                "total_votes": 10,
+                "items": [
+                  {
+                    "text": "yes",
+                    "percentage": "60%",
+                    "percentage_value": 60.0,
+                    "estimated_votes": 6,
+                    "selected": false
+                  }
+                ]
+              },
+              "like": 5,
+              "comments": [
+                {
+                  "author": "@a",
+                  "author_channel": {
+                    "url": null,
+                    "channel_id": null,
+                    "handle": "@a",
+                    "avatar": null
+                  },
+                  "content": [
+                    {
+                      "type": "emote",
+                      "name": null,
+                      "url": "https://yt3.ggpht.com/abc=w24-h24-c-k-nd"
+                    }
+                  ],
+                  "publish_time": {
+                    "text": "2 ชั่วโมงที่ผ่านมา",
+                    "earliest": "2024-01-17T09:00:00Z",
+                    "latest": "2024-01-17T10:00:00Z"
+                  },
+                  "url": "/post/Ugkx?lc=Ugz",
+                  "comment_id": "Ugz",
+                  "parent_comment_id": null,
+                  "post_id": "Ugkx",
+                  "sponsor_duration": null,
+                  "sponsor_badge": null,
+                  "like": 0
+                }
+              ],
+              "total_comment": 1
+            }
+          ]
+        }
+        "##);
+    }
+
+    #[test]
+    fn test_migrate_v2() {
+        let v2 = json!({
+            "schema_version": 2,
+            "parser_version": 4,
+            "channel": null,
+            "generated_at": "2024-01-18T00:00:00Z",
+            "posts": [{
+                "meta": {
+                    "source_created_at": null,
+                    "source_modified_at": null,
+                    "processed_at": "2024-01-18T00:00:00Z",
+                    "process_time": "PT0.01S"
+                },
+                "id": "Ugkx",
+                "author": "Yozora Mel",
+                "publish_time": { "text": "1 day ago", "earliest": null, "latest": null },
+                "sponsor_only": "Members only\nNight Sky",
+                "content": [],
+                "content_attachment": null,
+                "poll_attachment": null,
+                "like": 5,
+                "comments": [{
+                    "author": "@a",
+                    "content": [],
+                    "publish_time": { "text": "1 day ago", "earliest": null, "latest": null },
+                    "url": "/post/Ugkx?lc=Ugz",
+                    "sponsor_duration": "สมาชิกใหม่",
+                    "sponsor_badge": null,
+                    "like": 0,
+                    "replies": [{
+                        "author": "@b",
+                        "content": [],
+                        "publish_time": { "text": "1日前（編集済み）", "earliest": null, "latest": null },
+                        "url": "/post/Ugkx?lc=Ugz.Ugy",
+                        "sponsor_duration": "メンバー（2 年）",
+                        "sponsor_badge": null,
+                        "like": 0
+                    }]
+                }],
+                "total_comment": 2
+            }]
+        });
+
+        let (from, archive) = migrate(v2).unwrap();
+        assert_eq!(from, 2);
+        assert_eq!(archive.schema_version, SCHEMA_VERSION);
+        let post = &archive.posts[0].post;
+        assert_eq!(
+            post.main.sponsor_only,
+            Some(SponsorOnly {
+                text: "Members only\nNight Sky".to_owned(),
+                tier: Some("Night Sky".to_owned()),
+            })
+        );
+        let thread = &post.comments.as_ref().unwrap()[0];
+        assert_eq!(
+            thread.comment.sponsor_duration,
+            Some(SponsorDuration {
+                text: "สมาชิกใหม่".to_owned(),
+                months: Some(0),
+                new_member: true,
+            })
+        );
+        assert_eq!(
+            thread.replies[0]
+                .comment
+                .sponsor_duration
+                .as_ref()
+                .and_then(|d| d.months),
+            Some(24)
+        );
+        assert!(!thread.comment.edited);
+        assert!(thread.replies[0].comment.edited);
+        assert_eq!(post.main.author_channel, AuthorChannel::default());
+        let reply = &thread.replies[0].comment;
+        assert_eq!(
+            (
+                reply.comment_id.as_deref(),
+                reply.parent_comment_id.as_deref(),
+                reply.post_id.as_deref()
+            ),
+            (Some("Ugz.Ugy"), Some("Ugz"), Some("Ugkx"))
+        );
+        assert!(post.comment_id_mismatches().is_empty());
+        assert_eq!(
+            thread.replies[0].comment.author_channel,
+            AuthorChannel {
+                handle: Some("@b".to_owned()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let err = migrate(json!({ "schema_version": SCHEMA_VERSION + 1 })).unwrap_err();
+        assert!(err.to_string().contains("is newer than"), "{err}");
+        assert!(migrate(json!("posts")).is_err());
+    }
+}
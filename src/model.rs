@@ -0,0 +1,419 @@
+use itertools::Itertools;
+use std::ops::Not;
+
+use crate::{
+    link::LinkTarget,
+    membership::{SponsorDuration, SponsorOnly},
+    publish_time::PublishTime,
+    rich_text::RichText,
+};
+
+/// Version of the posts.json shape, bump whenever the model changes and add a migration step in [`crate::migrate`].
+pub const SCHEMA_VERSION: u32 = 6;
+
+/// Top level of posts.json.
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Archive {
+    pub schema_version: u32,
+    /// Version of the parser output the posts were parsed with, `None` if migrated from before it was recorded.
+    pub parser_version: Option<u32>,
+    /// Channel the posts belong to, `None` if there are no posts.
+    pub channel: Option<Channel>,
+    pub generated_at: jiff::Timestamp,
+    pub posts: Vec<PostWithMeta>,
+}
+
+impl Archive {
+    pub fn new(posts: Vec<PostWithMeta>, parser_version: Option<u32>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            parser_version,
+            channel: Channel::from_posts(&posts),
+            generated_at: jiff::Timestamp::now(),
+            posts,
+        }
+    }
+
+    /// JSON Schema of posts.json.
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(Archive)
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Channel {
+    pub name: String,
+}
+
+impl Channel {
+    /// Channel of the posts, named by their most common author.
+    pub fn from_posts(posts: &[PostWithMeta]) -> Option<Self> {
+        let name = posts
+            .iter()
+            .map(|post| post.post.main.author.as_str())
+            .counts()
+            .into_iter()
+            .max_by_key(|&(name, count)| (count, std::cmp::Reverse(name)))?
+            .0;
+        Some(Self {
+            name: name.to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct PostWithMeta {
+    pub meta: Meta,
+    #[serde(flatten)]
+    pub post: Post,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Meta {
+    pub source_created_at: Option<jiff::Timestamp>,
+    pub source_modified_at: Option<jiff::Timestamp>,
+    pub processed_at: jiff::Timestamp,
+    pub process_time: jiff::SignedDuration,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Post {
+    pub id: String,
+    #[serde(flatten)]
+    pub main: Main,
+    pub comments: Option<Vec<CommentThread>>,
+    pub total_comment: Option<u32>,
+}
+
+impl Post {
+    /// Comments whose ids decoded from their url disagree with where they are in the post,
+    /// ie. a reply nested under a thread other than its parent. Comments without ids are skipped.
+    pub fn comment_id_mismatches(&self) -> Vec<CommentIdMismatch> {
+        fn check(
+            post_id: &str,
+            thread: &CommentThread,
+            parent: Option<&Comment>,
+            out: &mut Vec<CommentIdMismatch>,
+        ) {
+            let comment = &thread.comment;
+            let mut mismatch = |field, expected: Option<&str>, found: Option<&str>| {
+                if expected != found {
+                    out.push(CommentIdMismatch {
+                        post_id: post_id.to_owned(),
+                        url: comment.url.clone(),
+                        field,
+                        expected: expected.map(str::to_owned),
+                        found: found.map(str::to_owned),
+                    });
+                }
+            };
+            // ids are only known when the url is a permalink
+            if comment.comment_id.is_some() {
+                mismatch("post_id", Some(post_id), comment.post_id.as_deref());
+                // replies of replies still belong to the top-level comment
+                let expected_parent =
+                    parent.and_then(|p| p.parent_comment_id.as_deref().or(p.comment_id.as_deref()));
+                if parent.is_none() || expected_parent.is_some() {
+                    mismatch(
+                        "parent_comment_id",
+                        expected_parent,
+                        comment.parent_comment_id.as_deref(),
+                    );
+                }
+            }
+            for reply in &thread.replies {
+                check(post_id, reply, Some(comment), out);
+            }
+        }
+
+        let mut mismatches = Vec::new();
+        for thread in self.comments.iter().flatten() {
+            check(&self.id, thread, None, &mut mismatches);
+        }
+        mismatches
+    }
+}
+
+/// Id of a comment that disagrees with where it is in a post, see [`Post::comment_id_mismatches`].
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct CommentIdMismatch {
+    pub post_id: String,
+    /// Url of the comment.
+    pub url: String,
+    /// `post_id` or `parent_comment_id`.
+    pub field: &'static str,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Main {
+    pub author: String,
+    pub author_channel: AuthorChannel,
+    pub publish_time: PublishTime,
+    /// Members-only badge, `None` for public posts.
+    pub sponsor_only: Option<SponsorOnly>,
+
+    pub content: RichText,
+    pub content_attachment: Option<ContentAttachment>,
+    pub poll_attachment: Option<PollAttachment>,
+    pub like: u32,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct ContentAttachment {
+    pub images: Vec<String>,
+    pub links: Vec<Link>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Link {
+    /// Href as found in the page.
+    pub url: String,
+    pub target: LinkTarget,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct PollAttachment {
+    pub total_votes: u32,
+    pub items: Vec<PollItem>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct PollItem {
+    pub text: String,
+    /// Percentage as displayed, ie. `60%`.
+    pub percentage: String,
+    /// Percentage as a number, `None` if results are not shown.
+    #[serde(default)]
+    pub percentage_value: Option<f64>,
+    /// Vote count estimated from the rounded percentage and `total_votes`.
+    #[serde(default)]
+    pub estimated_votes: Option<u32>,
+    /// Option image of an image poll.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Whether the archiving account voted for this option.
+    #[serde(default)]
+    pub selected: bool,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: Comment,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<CommentThread>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct Comment {
+    /// Name as displayed, the `@handle` for most comments.
+    pub author: String,
+    pub author_channel: AuthorChannel,
+    pub content: RichText,
+    pub publish_time: PublishTime,
+    pub url: String,
+    /// Id decoded from `url`, `<thread>.<reply>` for replies. `None` if `url` is not a comment's permalink.
+    pub comment_id: Option<String>,
+    /// Id of the top-level comment a reply belongs to, decoded from `url`.
+    pub parent_comment_id: Option<String>,
+    /// Id of the post decoded from `url`.
+    pub post_id: Option<String>,
+    /// Membership of the author, `None` if they are not a member.
+    pub sponsor_duration: Option<SponsorDuration>,
+    pub sponsor_badge: Option<String>,
+    pub like: u32,
+    /// Hearted by the channel owner.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub hearted: bool,
+    /// Pinned to the top of the comments by the channel owner.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub pinned: bool,
+    /// Written by the channel owner, shown with their name highlighted.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub channel_owner: bool,
+    /// Badge next to the author's name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_badge: Option<AuthorBadge>,
+    /// Marked as edited next to the publish time.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub edited: bool,
+}
+
+/// Channel of a post or comment author, linked from their name and avatar.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct AuthorChannel {
+    /// Href as found in the page, ie. `/@handle` or `/channel/UC...`.
+    pub url: Option<String>,
+    /// `UC...` id, only known when linked by id.
+    pub channel_id: Option<String>,
+    /// Handle including the leading `@`, decoded.
+    pub handle: Option<String>,
+    /// Avatar image url.
+    pub avatar: Option<String>,
+}
+
+impl AuthorChannel {
+    /// Identify the author from the `url` of their name or avatar, falling back to the displayed `name` for the handle.
+    pub fn new(url: Option<String>, name: &str, avatar: Option<String>) -> Self {
+        let target = url.as_deref().map(LinkTarget::classify);
+        let (channel_id, handle) = match target {
+            Some(LinkTarget::Channel { id }) => (Some(id), None),
+            Some(LinkTarget::Handle { handle }) => (None, Some(handle)),
+            _ => (None, None),
+        };
+        let handle = handle.or_else(|| name.starts_with('@').then(|| name.to_owned()));
+        Self {
+            url,
+            channel_id,
+            handle,
+            avatar,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorBadge {
+    Verified,
+    /// Official artist channel.
+    Artist,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::locale::Locale;
+
+    /// Post with only an id, for tests.
+    pub(crate) fn post(id: &str) -> PostWithMeta {
+        PostWithMeta {
+            meta: Meta {
+                source_created_at: None,
+                source_modified_at: None,
+                processed_at: jiff::Timestamp::UNIX_EPOCH,
+                process_time: jiff::SignedDuration::ZERO,
+            },
+            post: Post {
+                id: id.to_owned(),
+                main: Main {
+                    author: "author".to_owned(),
+                    author_channel: AuthorChannel::default(),
+                    publish_time: PublishTime::resolve("1 ปีที่แล้ว".to_owned(), Locale::Th, None),
+                    sponsor_only: None,
+                    content: RichText::default(),
+                    content_attachment: None,
+                    poll_attachment: None,
+                    like: 1,
+                },
+                comments: None,
+                total_comment: None,
+            },
+        }
+    }
+
+    /// Comment with plain text content and its replies, for tests.
+    pub(crate) fn comment(author: &str, text: &str, replies: Vec<CommentThread>) -> CommentThread {
+        let mut content = RichText::default();
+        content.push_text(text, crate::rich_text::Style::default());
+        CommentThread {
+            comment: Comment {
+                author: author.to_owned(),
+                author_channel: AuthorChannel::new(Some(format!("/{author}")), author, None),
+                content,
+                publish_time: PublishTime::resolve("1 ปีที่แล้ว".to_owned(), Locale::Th, None),
+                url: "/post/a?lc=Ugz".to_owned(),
+                comment_id: Some("Ugz".to_owned()),
+                parent_comment_id: None,
+                post_id: Some("a".to_owned()),
+                sponsor_duration: None,
+                sponsor_badge: None,
+                like: 0,
+                hearted: false,
+                pinned: false,
+                channel_owner: false,
+                author_badge: None,
+                edited: false,
+            },
+            replies,
+        }
+    }
+
+    #[test]
+    fn test_comment_id_mismatches() {
+        let reply = |url: &str| {
+            let mut reply = comment("@r", "reply", vec![]);
+            let ids = crate::link::CommentUrl::parse(url).unwrap();
+            reply.comment.url = url.to_owned();
+            reply.comment.comment_id = Some(ids.comment_id);
+            reply.comment.parent_comment_id = ids.parent_comment_id;
+            reply.comment.post_id = Some(ids.post_id);
+            reply
+        };
+        let mut a = post("a");
+        let mut nested = reply("/post/a?lc=Ugz.Ugy");
+        let mut no_permalink = comment("@n", "reply", vec![]);
+        no_permalink.comment.url = "/channel/UCx/community".to_owned();
+        no_permalink.comment.comment_id = None;
+        no_permalink.comment.post_id = None;
+        nested.replies = vec![reply("/post/a?lc=Ugz.Ugx"), no_permalink];
+        a.post.comments = Some(vec![
+            comment(
+                "@x",
+                "thread",
+                vec![
+                    nested,
+                    reply("/post/a?lc=Ugw.Ugv"),
+                    reply("/post/b?lc=Ugz.Ugu"),
+                ],
+            ),
+            reply("/post/a?lc=Ugz.Ugt"),
+        ]);
+
+        let mismatches = a
+            .post
+            .comment_id_mismatches()
+            .into_iter()
+            .map(|m| (m.url, m.field, m.expected, m.found))
+            .collect::<Vec<_>>();
+        let some = |id: &str| Some(id.to_owned());
+        assert_eq!(
+            mismatches,
+            [
+                (
+                    "/post/a?lc=Ugw.Ugv".to_owned(),
+                    "parent_comment_id",
+                    some("Ugz"),
+                    some("Ugw")
+                ),
+                (
+                    "/post/b?lc=Ugz.Ugu".to_owned(),
+                    "post_id",
+                    some("a"),
+                    some("b")
+                ),
+                (
+                    "/post/a?lc=Ugz.Ugt".to_owned(),
+                    "parent_comment_id",
+                    None,
+                    some("Ugz")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_up_to_date() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/posts.schema.json");
+        let expected = serde_json::to_string_pretty(&Archive::json_schema()).unwrap() + "\n";
+        assert!(
+            std::fs::read_to_string(path).is_ok_and(|schema| schema == expected),
+            "{path} is outdated, regenerate it with `cargo run -- schema > schema/posts.schema.json`"
+        );
+    }
+}
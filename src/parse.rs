@@ -0,0 +1,1360 @@
+use itertools::Itertools;
+use scraper::{CaseSensitivity, ElementRef, Node};
+use std::ops::Not;
+
+use crate::{
+    emote::{Emoji, EmoteResolver, post_url},
+    error::{
+        ParseError, ParseResult, ParseResultExt, attr, select_all, select_at_most_one,
+        select_first, select_one,
+    },
+    link::{CommentUrl, LinkTarget},
+    locale::Locale,
+    membership::{SponsorDuration, SponsorOnly},
+    model::{
+        AuthorBadge, AuthorChannel, Comment, CommentThread, ContentAttachment, Link, Main,
+        PollAttachment, PollItem, Post,
+    },
+    publish_time::PublishTime,
+    rich_text::{RichText, Span, Style},
+};
+
+/// Version of the parser output, bump whenever parsing changes to invalidate cached posts.
+pub const PARSER_VERSION: u32 = 8;
+
+/// Configuration of parsing a post.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseContext<'a> {
+    pub emote_resolver: &'a EmoteResolver,
+    /// UI language the post was saved in, use for parsing numbers.
+    /// Detected from the page's `<html lang>` if `None`, falling back to Thai.
+    pub locale: Option<Locale>,
+    /// Time the post was captured, use for resolving relative publish times.
+    pub captured_at: Option<jiff::Timestamp>,
+}
+
+impl<'a> ParseContext<'a> {
+    pub fn new(emote_resolver: &'a EmoteResolver) -> Self {
+        Self {
+            emote_resolver,
+            locale: None,
+            captured_at: None,
+        }
+    }
+
+    fn locale(&self) -> Locale {
+        self.locale.unwrap_or_default()
+    }
+}
+
+pub fn parse(content: &str, id: String, cx: &ParseContext<'_>) -> ParseResult<Post> {
+    let html = scraper::Html::parse_document(content);
+    let cx = ParseContext {
+        locale: cx.locale.or_else(|| Locale::detect(&html)),
+        ..*cx
+    };
+
+    parse_document(&html, &id, &cx)
+        .map(|(main, comments, total_comment)| Post {
+            id: id.clone(),
+            main,
+            comments,
+            total_comment,
+        })
+        .map_err(|err| err.with_post_id(id))
+}
+
+type ParsedDocument = (Main, Option<Vec<CommentThread>>, Option<u32>);
+
+fn parse_document(
+    html: &scraper::Html,
+    id: &str,
+    cx: &ParseContext<'_>,
+) -> ParseResult<ParsedDocument> {
+    let root = html.root_element();
+    let content_items = select_all(root, "body>#contents>*");
+    let (post, comment) = match content_items[..] {
+        [post] => (post, None),
+        [post, comment] => (post, Some(comment)),
+        _ => {
+            return Err(ParseError::at(
+                root,
+                "body>#contents>*",
+                format!(
+                    "expected post and optional comments, found {} elements",
+                    content_items.len()
+                ),
+            ));
+        }
+    };
+    match comment {
+        Some(comment) if comment.value().name() != "ytd-comments" => {
+            return Err(ParseError::new(format!(
+                "expected <ytd-comments>, found <{}>",
+                comment.value().name()
+            ))
+            .with_excerpt(comment));
+        }
+        // ensure missing comments are due to comments being turned off
+        None if !post
+            .html()
+            .contains("support.google.com/youtube/answer/9706180") =>
+        {
+            return Err(
+                ParseError::new("comments are missing but not turned off").with_excerpt(post)
+            );
+        }
+        _ => {}
+    }
+
+    let main = select_one(post, "#post>*>#body #main")?;
+    let main = parse_main(main, id, cx).context_with(|| "post".to_owned())?;
+
+    let (total_comment, comments) = if let Some(comment) = comment {
+        let (total_comment, comments) = parse_comments(comment, cx)?;
+        (Some(total_comment), Some(comments))
+    } else {
+        (None, None)
+    };
+
+    Ok((main, comments, total_comment))
+}
+
+fn parse_main(main: scraper::ElementRef<'_>, id: &str, cx: &ParseContext<'_>) -> ParseResult<Main> {
+    let author = select_one(main, "#author-text")?;
+    let author_text = author.text().map(|s| s.trim()).collect::<String>();
+    let author_channel =
+        parse_author_channel(main, author, &author_text).context_with(|| "author".to_owned())?;
+    let publish_time = select_one(main, "#published-time-text")?
+        .text()
+        .collect::<String>();
+    let publish_time = PublishTime::resolve(publish_time, cx.locale(), cx.captured_at);
+    let sponsor_only = select_at_most_one(main, "#sponsors-only-badge")?
+        .map(|s| {
+            s.text()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .join("\n")
+        })
+        .filter(|s| !s.is_empty())
+        .map(|text| SponsorOnly::parse(text, cx.locale()));
+    let content = select_first(main, "#content")?;
+    let content = select_at_most_one(content, "#content-text")?.unwrap_or(content);
+    let content =
+        parse_rich_text(content, cx, &post_url(id)).context_with(|| "content".to_owned())?;
+    let content_attachment = select_all(main, "#content-attachment:not([hidden])")
+        .into_iter()
+        .next()
+        .map(|c| -> ParseResult<ContentAttachment> {
+            let images = select_all(c, "img[src]")
+                .into_iter()
+                .map(|img| Ok(attr(img, "src")?.to_owned()))
+                .collect::<ParseResult<Vec<_>>>()?;
+            let links = select_all(c, "a[href]")
+                .into_iter()
+                .map(|link| Ok(attr(link, "href")?.to_owned()))
+                .collect::<ParseResult<Vec<_>>>()?
+                .into_iter()
+                .filter(|link| {
+                    // filter out self-link to current channel
+                    link.contains("/@").not()
+                })
+                .dedup()
+                .map(|url| Link {
+                    target: LinkTarget::classify(&url),
+                    url,
+                })
+                .collect();
+
+            Ok(ContentAttachment { images, links })
+        })
+        .transpose()?;
+    let poll_attachment = select_at_most_one(main, "#poll-attachment:not([hidden])")?
+        .map(|poll| parse_poll(poll, cx.locale()))
+        .transpose()
+        .context_with(|| "poll attachment".to_owned())?;
+
+    let like = select_first(main, "#vote-count-middle")?;
+    let like = parse_like(like, cx.locale())?;
+
+    Ok(Main {
+        author: author_text,
+        author_channel,
+        publish_time,
+        sponsor_only,
+        content,
+        content_attachment,
+        poll_attachment,
+        like,
+    })
+}
+
+fn parse_poll(poll: scraper::ElementRef<'_>, locale: Locale) -> ParseResult<PollAttachment> {
+    let total_votes = select_one(poll, "#vote-info")?.text().collect::<String>();
+    let total_votes = locale.parse_total_votes(&total_votes).map_err(|err| {
+        ParseError::new(format!(
+            "total votes should be a parseable number: {total_votes} ({err})"
+        ))
+        .with_excerpt(poll)
+    })?;
+
+    let items = select_all(poll, "a[role='option']")
+        .into_iter()
+        .enumerate()
+        .map(|(i, option)| {
+            parse_poll_item(option, total_votes).context_with(|| format!("option [{i}]"))
+        })
+        .collect::<ParseResult<_>>()?;
+
+    Ok(PollAttachment { total_votes, items })
+}
+
+fn parse_poll_item(option: scraper::ElementRef<'_>, total_votes: u32) -> ParseResult<PollItem> {
+    let info = select_one(option, ".choice-info")?;
+    let text = select_one(info, ".choice-text")?.text().collect::<String>();
+    let percentage = select_one(info, ".vote-percentage")?;
+    let percentage_value = parse_percentage(percentage)?;
+    let percentage = percentage.text().collect::<String>();
+    let estimated_votes = percentage_value.map(|p| estimate_votes(total_votes, p));
+    let image = select_at_most_one(option, "img[src]")?
+        .map(|img| Ok(attr(img, "src")?.to_owned()))
+        .transpose()?;
+    let selected =
+        option.attr("aria-selected") == Some("true") || option.attr("selected").is_some();
+
+    Ok(PollItem {
+        text,
+        percentage,
+        percentage_value,
+        estimated_votes,
+        image,
+        selected,
+    })
+}
+
+/// Votes of a poll option, estimated from its rounded percentage of `total_votes`.
+pub(crate) fn estimate_votes(total_votes: u32, percentage: f64) -> u32 {
+    (f64::from(total_votes) * percentage / 100.0).round() as u32
+}
+
+/// Parse vote percentage text (ie. `60%`), empty text means results are not shown.
+pub(crate) fn percentage_value(text: &str) -> Result<Option<f64>, std::num::ParseFloatError> {
+    let trimmed = text.trim().trim_end_matches('%').trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    trimmed.parse().map(Some)
+}
+
+fn parse_percentage(percentage: scraper::ElementRef<'_>) -> ParseResult<Option<f64>> {
+    let text = percentage.text().collect::<String>();
+    percentage_value(&text).map_err(|err| {
+        ParseError::new(format!(
+            "vote percentage should be a parseable number: {text:?} ({err})"
+        ))
+        .with_excerpt(percentage)
+    })
+}
+
+fn parse_like(like: scraper::ElementRef<'_>, locale: Locale) -> ParseResult<u32> {
+    let text = like.text().collect::<String>();
+    locale.parse_vote(&text).map_err(|err| {
+        ParseError::new(format!(
+            "like should be a parseable number: {text:?} ({err})"
+        ))
+        .with_excerpt(like)
+    })
+}
+
+fn parse_comments(
+    comment: scraper::ElementRef<'_>,
+    cx: &ParseContext<'_>,
+) -> ParseResult<(u32, Vec<CommentThread>)> {
+    // TODO: used to validate
+    let count = select_one(comment, "#count").context_with(|| "comments' count".to_owned())?;
+    let n: u32 = count
+        .text()
+        .find_map(|t| cx.locale().parse_compact_number(t).ok())
+        .ok_or_else(|| ParseError::new("comments' count should be a number").with_excerpt(count))?;
+
+    let threads = select_all(
+        comment,
+        "#contents>ytd-comment-thread-renderer:not([is-sub-thread])",
+    );
+    let comments = threads
+        .into_iter()
+        .enumerate()
+        .map(|(i, thread)| {
+            parse_comment_thread(thread, cx).context_with(|| format!("comment thread [{}]", i))
+        })
+        .collect::<ParseResult<Vec<_>>>()?;
+
+    // cannot compare exactly because youtube may hide some comments
+    // ensure!(
+    //     n >= comments.len() as u32,
+    //     "provided comment count ({}) should be at least the number of visible comments ({})",
+    //     n,
+    //     comments.len()
+    // );
+
+    Ok((n, comments))
+}
+
+fn parse_comment_thread(
+    thread: scraper::ElementRef<'_>,
+    cx: &ParseContext<'_>,
+) -> ParseResult<CommentThread> {
+    struct CommentTree<'a> {
+        comment: ElementRef<'a>,
+        children: Vec<CommentTree<'a>>,
+    }
+
+    fn build_comment_trees(thread: ElementRef<'_>) -> ParseResult<CommentTree<'_>> {
+        if thread.value().name() != "ytd-comment-thread-renderer" {
+            return Err(ParseError::new(format!(
+                "expected <ytd-comment-thread-renderer>, found <{}>",
+                thread.value().name()
+            ))
+            .with_excerpt(thread));
+        }
+
+        // Find direct comment, not inside replies (relative to current element)
+        let comment = select_one(thread, "#comment:not(:scope #replies *)")?;
+
+        // Find direct children threads, exactly one level inside replies.
+        // Build comment tree from them.
+        let children = select_all(
+            thread,
+            "#replies:not([hidden]) ytd-comment-thread-renderer:not(:scope #replies #replies *)",
+        )
+        .into_iter()
+        .map(build_comment_trees)
+        .collect::<ParseResult<Vec<_>>>()?;
+
+        Ok(CommentTree { comment, children })
+    }
+
+    let tree = build_comment_trees(thread)?;
+
+    let comment = parse_comment(tree.comment, cx)?;
+
+    // If the tree has only one node then it's either from before youtube thread comment or have no replies.
+    // Parsing with pre thread comment method should handle both case
+    if tree.children.is_empty() {
+        let replies = select_all(thread, "#replies:not([hidden]) #contents>*")
+            .into_iter()
+            .enumerate()
+            .map(|(i, e)| {
+                Ok(CommentThread {
+                    comment: parse_comment(e, cx).context_with(|| format!("reply [{}]", i))?,
+                    replies: Vec::new(),
+                })
+            })
+            .collect::<ParseResult<_>>()?;
+        return Ok(CommentThread { comment, replies });
+    }
+
+    fn build_replies(tree: &CommentTree<'_>, cx: &ParseContext<'_>) -> ParseResult<CommentThread> {
+        let comment = parse_comment(tree.comment, cx)?;
+        let replies = tree
+            .children
+            .iter()
+            .enumerate()
+            .map(|(i, c)| build_replies(c, cx).context_with(|| format!("reply [{}]", i)))
+            .collect::<ParseResult<Vec<_>>>()?;
+        Ok(CommentThread { comment, replies })
+    }
+
+    build_replies(&tree, cx)
+}
+
+fn parse_comment(comment: scraper::ElementRef<'_>, cx: &ParseContext<'_>) -> ParseResult<Comment> {
+    let author_element = select_one(comment, "#author-text")
+        .context_with(|| "comment should has exactly one author".to_owned())?;
+    let author = author_element.text().map(|s| s.trim()).collect::<String>();
+    let author_channel = parse_author_channel(comment, author_element, &author)
+        .context_with(|| "comment's author".to_owned())?;
+
+    let publish_time = select_one(comment, "#published-time-text")
+        .context_with(|| "comment should has exactly one publish time".to_owned())?
+        .text()
+        .map(|s| s.trim())
+        .collect::<String>();
+    let publish_time = PublishTime::resolve(publish_time, cx.locale(), cx.captured_at);
+
+    let url = select_one(comment, "#published-time-text a[href]")
+        .context_with(|| "comment should has exactly one url".to_owned())?;
+    let url = attr(url, "href")?.to_owned();
+    let (comment_id, parent_comment_id, post_id) = match CommentUrl::parse(&url) {
+        Some(ids) => (
+            Some(ids.comment_id),
+            ids.parent_comment_id,
+            Some(ids.post_id),
+        ),
+        None => (None, None, None),
+    };
+
+    let sponsor = select_at_most_one(
+        comment,
+        "#sponsor-comment-badge>ytd-sponsor-comment-badge-renderer",
+    )
+    .context_with(|| "comment should has at most one sponsor duration".to_owned())?;
+    let sponsor_duration = sponsor
+        .map(|ele| -> ParseResult<SponsorDuration> {
+            let label = attr(ele, "aria-label").context_with(|| {
+                "comment's sponsor's aria-label should exists for sponsor duration".to_owned()
+            })?;
+            Ok(SponsorDuration::parse(label.to_owned(), cx.locale()))
+        })
+        .transpose()?;
+    let sponsor_badge = sponsor
+        .map(|ele| -> ParseResult<Option<String>> {
+            select_at_most_one(ele, "img[src]")
+                .context_with(|| {
+                    "comment's sponsor's badge should not be more than one".to_owned()
+                })?
+                .map(|ele| Ok(attr(ele, "src")?.to_owned()))
+                .transpose()
+        })
+        .transpose()?
+        .flatten();
+
+    let like = select_first(comment, "#vote-count-middle")
+        .context_with(|| "comment should has at least one like".to_owned())?;
+    let like = parse_like(like, cx.locale())?;
+
+    let content = select_one(comment, "#content-text>*")
+        .context_with(|| "comment should has exactly one content".to_owned())?;
+    let content = parse_rich_text(content, cx, &url)?;
+
+    let hearted = select_at_most_one(comment, "#creator-heart ytd-creator-heart-renderer")
+        .context_with(|| "comment should has at most one creator heart".to_owned())?
+        .is_some();
+    let pinned = select_at_most_one(
+        comment,
+        "#pinned-comment-badge ytd-pinned-comment-badge-renderer:not([hidden])",
+    )
+    .context_with(|| "comment should has at most one pinned badge".to_owned())?
+    .is_some();
+    let author_badge = select_at_most_one(
+        comment,
+        "#author-comment-badge>ytd-author-comment-badge-renderer",
+    )
+    .context_with(|| "comment should has at most one author badge".to_owned())?;
+    // the channel owner's name is shown in the badge, highlighted
+    let channel_owner = author_badge.is_some_and(|badge| badge.attr("creator").is_some());
+    let author_badge = author_badge
+        .map(|badge| -> ParseResult<Option<AuthorBadge>> {
+            let tooltip = select_at_most_one(badge, "#tooltip")?
+                .map(|tooltip| tooltip.text().collect::<String>())
+                .unwrap_or_default();
+            let tooltip = tooltip.trim();
+            Ok((!tooltip.is_empty()).then(|| {
+                if cx.locale().is_artist_badge(tooltip) {
+                    AuthorBadge::Artist
+                } else {
+                    AuthorBadge::Verified
+                }
+            }))
+        })
+        .transpose()
+        .context_with(|| "author badge".to_owned())?
+        .flatten();
+    let edited = cx.locale().is_edited(&publish_time.text);
+
+    Ok(Comment {
+        author,
+        author_channel,
+        publish_time,
+        url,
+        comment_id,
+        parent_comment_id,
+        post_id,
+        sponsor_duration,
+        sponsor_badge,
+        like,
+        content,
+        hearted,
+        pinned,
+        channel_owner,
+        author_badge,
+        edited,
+    })
+}
+
+/// Channel of a post or comment author, from the links on their name (`author`) and avatar in `element`.
+fn parse_author_channel(
+    element: ElementRef<'_>,
+    author: ElementRef<'_>,
+    name: &str,
+) -> ParseResult<AuthorChannel> {
+    let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_owned());
+    let url = match author.attr("href").and_then(non_empty) {
+        Some(url) => Some(url),
+        // avatar is the link itself on posts, and wraps a link on comments
+        None => select_at_most_one(
+            element,
+            "#author-thumbnail[href], #author-thumbnail a[href]",
+        )?
+        .and_then(|link| link.attr("href").and_then(non_empty)),
+    };
+    let avatar = select_at_most_one(element, "#author-thumbnail img[src]")?
+        .and_then(|img| img.attr("src").and_then(non_empty));
+    Ok(AuthorChannel::new(url, name, avatar))
+}
+
+/// Parse formatted text, from either `<yt-attributed-string>` or `<yt-formatted-string>`.
+/// `source` is the url of the post or comment, recorded with the emotes.
+fn parse_rich_text(
+    element: ElementRef<'_>,
+    cx: &ParseContext<'_>,
+    source: &str,
+) -> ParseResult<RichText> {
+    let mut text = RichText::default();
+    for child in element.children() {
+        parse_rich_text_node(child, Style::default(), cx, source, &mut text)?;
+    }
+    text.trim();
+    Ok(text)
+}
+
+fn parse_rich_text_node(
+    node: ego_tree::NodeRef<'_, Node>,
+    style: Style,
+    cx: &ParseContext<'_>,
+    source: &str,
+    out: &mut RichText,
+) -> ParseResult<()> {
+    let e = match node.value() {
+        Node::Text(text) => {
+            out.push_text(text, style);
+            return Ok(());
+        }
+        Node::Comment(_) => return Ok(()),
+        Node::Element(_) => ElementRef::wrap(node).expect("node is an element"),
+        node => return Err(ParseError::new(format!("unexpected node: {node:?}"))),
+    };
+
+    match e.value().name() {
+        // Styled text or container of other items
+        "span" | "yt-formatted-string" => {
+            let style = text_style(e, style);
+            for child in e.children() {
+                parse_rich_text_node(child, style, cx, source, out)?;
+            }
+        }
+        // Some kind of link
+        "a" => {
+            let href = attr(e, "href")?;
+            out.push_link(&e.text().collect::<String>(), href, style);
+        }
+        // Likely an emoji
+        "img" => {
+            let src = attr(e, "src")?;
+            let alt = e.attr("alt");
+            out.0
+                .push(match cx.emote_resolver.resolve_emoji(src, alt, source) {
+                    Some(Emoji::Unicode(emoji)) => Span::Emoji { emoji },
+                    Some(Emoji::Custom(name)) => Span::Emote {
+                        name: Some(name),
+                        url: src.to_owned(),
+                    },
+                    None => Span::Emote {
+                        name: None,
+                        url: src.to_owned(),
+                    },
+                });
+        }
+        "br" => out.push_text("\n", style),
+        name => {
+            return Err(ParseError::new(format!("unexpected element <{name}>")).with_excerpt(e));
+        }
+    }
+    Ok(())
+}
+
+/// Style of `e`, from either its inline style or yt-formatted-string's classes.
+fn text_style(e: ElementRef<'_>, parent: Style) -> Style {
+    let css = e.attr("style").unwrap_or_default();
+    let class = |name| e.value().has_class(name, CaseSensitivity::CaseSensitive);
+    Style {
+        bold: parent.bold
+            || css.contains("font-weight: 500")
+            || css.contains("font-weight: bold")
+            || class("bold"),
+        italic: parent.italic || css.contains("font-style: italic") || class("italic"),
+        strikethrough: parent.strikethrough
+            || css.contains("line-through")
+            || class("strikethrough"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Element;
+
+    mod content_item {
+        use insta::assert_json_snapshot;
+        use std::collections::HashMap;
+
+        use super::*;
+
+        fn parse_rich_text_fragment(html: &str) -> ParseResult<RichText> {
+            let resolver = EmoteResolver::with_mapping(HashMap::default());
+            let html = scraper::Html::parse_fragment(html);
+            parse_rich_text(
+                html.root_element(),
+                &ParseContext::new(&resolver),
+                "/post/test",
+            )
+        }
+
+        #[test]
+        fn test_attributed_string() {
+            let content = parse_rich_text_fragment(
+                r#"<span class="yt-core-attributed-string yt-core-attributed-string--white-space-pre-wrap" dir="auto"><span class="" dir="auto" style="font-weight: 400;">
+<a class="yt-core-attributed-string__link yt-core-attributed-string__link--call-to-action-color" tabindex="0" href="/channel/UCIRjccPaf9aWkNu5cxXJEUg" target="" force-new-state="true">&nbsp;@괜찮아이츠키&nbsp;</a></span>どーせ転生すんだろ笑笑すぐ会えるよー笑笑無意味過ぎる</span>"#,
+            )
+            .unwrap();
+
+            assert_json_snapshot!(content, @r#"
+            [
+              {
+                "type": "mention",
+                "text": "@괜찮아이츠키",
+                "url": "/channel/UCIRjccPaf9aWkNu5cxXJEUg",
+                "target": {
+                  "kind": "channel",
+                  "id": "UCIRjccPaf9aWkNu5cxXJEUg"
+                }
+              },
+              {
+                "type": "text",
+                "text": " どーせ転生すんだろ笑笑すぐ会えるよー笑笑無意味過ぎる"
+              }
+            ]
+            "#);
+        }
+
+        #[test]
+        fn test_per_call_emote_mapping() {
+            let html = scraper::Html::parse_fragment(
+                r#"<span><img src="https://yt3.ggpht.com/abc=w24-h24-c-k-nd"></span>"#,
+            );
+            let parse_with = |name: &str| {
+                let resolver =
+                    EmoteResolver::with_mapping([("abc=".to_owned(), name.to_owned())].into());
+                let content = parse_rich_text(
+                    html.root_element(),
+                    &ParseContext::new(&resolver),
+                    "/post/test",
+                )
+                .unwrap();
+                let [Span::Emote { name, .. }] = &content.0[..] else {
+                    panic!("expected a single emote: {content:?}");
+                };
+                name.clone()
+            };
+            assert_eq!(parse_with("a").as_deref(), Some("a"));
+            assert_eq!(parse_with("b").as_deref(), Some("b"));
+        }
+
+        #[test]
+        fn test_formatted_string() {
+            let content = parse_rich_text_fragment(
+                r#"<yt-formatted-string id="content-text" split-lines=""><span class="bold style-scope yt-formatted-string">Schedule</span><span class="style-scope yt-formatted-string"> for </span><a class="yt-simple-endpoint style-scope yt-formatted-string" href="/hashtag/amanetime">#amanetime</a><br><span class="italic strikethrough style-scope yt-formatted-string">cancelled</span><span class="style-scope yt-formatted-string"> see </span><a class="yt-simple-endpoint style-scope yt-formatted-string" href="/watch?v=dQw4w9WgXcQ&amp;t=83s">1:23</a> <a class="yt-simple-endpoint style-scope yt-formatted-string" href="https://www.youtube.com/redirect?q=https%3A%2F%2Fexample.com">example.com</a><img class="small-emoji emoji style-scope yt-formatted-string" src="https://yt3.ggpht.com/FrYgdeZPpvXs-6Mp305ZiimWJ0wV5bcVZctaUy80mnIdwe-P8HRGYAm0OyBtVx8EB9_Dxkc=w24-h24-c-k-nd" alt="Rawr"><img class="small-emoji emoji style-scope yt-formatted-string" src="https://www.youtube.com/s/gaming/emoji/7ff574f2/emoji_u1f499.png" alt="💙"></yt-formatted-string>"#,
+            )
+            .unwrap();
+
+            assert_json_snapshot!(content, @r##"
+            [
+              {
+                "type": "text",
+                "text": "Schedule",
+                "bold": true
+              },
+              {
+                "type": "text",
+                "text": " for "
+              },
+              {
+                "type": "hashtag",
+                "text": "#amanetime",
+                "url": "/hashtag/amanetime",
+                "target": {
+                  "kind": "hashtag",
+                  "tag": "amanetime"
+                }
+              },
+              {
+                "type": "text",
+                "text": "\n"
+              },
+              {
+                "type": "text",
+                "text": "cancelled",
+                "italic": true,
+                "strikethrough": true
+              },
+              {
+                "type": "text",
+                "text": " see "
+              },
+              {
+                "type": "timestamp",
+                "text": "1:23",
+                "url": "/watch?v=dQw4w9WgXcQ&t=83s",
+                "target": {
+                  "kind": "timestamp",
+                  "video_id": "dQw4w9WgXcQ",
+                  "seconds": 83
+                }
+              },
+              {
+                "type": "text",
+                "text": " "
+              },
+              {
+                "type": "link",
+                "text": "example.com",
+                "url": "https://www.youtube.com/redirect?q=https%3A%2F%2Fexample.com",
+                "target": {
+                  "kind": "external",
+                  "url": "https://example.com"
+                }
+              },
+              {
+                "type": "emote",
+                "name": "Rawr",
+                "url": "https://yt3.ggpht.com/FrYgdeZPpvXs-6Mp305ZiimWJ0wV5bcVZctaUy80mnIdwe-P8HRGYAm0OyBtVx8EB9_Dxkc=w24-h24-c-k-nd"
+              },
+              {
+                "type": "emoji",
+                "emoji": "💙"
+              }
+            ]
+            "##);
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        #[test]
+        fn test_missing_contents() {
+            let resolver = EmoteResolver::with_mapping(Default::default());
+            let err = parse(
+                r#"<html><body><div id="contents"></div></body></html>"#,
+                "Ugkx".to_owned(),
+                &ParseContext::new(&resolver),
+            )
+            .unwrap_err();
+            assert_eq!(err.post_id.as_deref(), Some("Ugkx"));
+            assert_eq!(err.selector.as_deref(), Some("body>#contents>*"));
+            assert!(err.excerpt.unwrap().contains(r#"<div id="contents">"#));
+        }
+
+        #[test]
+        fn test_unexpected_content_item() {
+            let html = scraper::Html::parse_fragment(r#"<div class="unexpected">text</div>"#);
+            let resolver = EmoteResolver::with_mapping(Default::default());
+            let err = parse_rich_text(
+                html.root_element(),
+                &ParseContext::new(&resolver),
+                "/post/test",
+            )
+            .unwrap_err();
+            assert_eq!(err.message, "unexpected element <div>");
+            assert_eq!(
+                err.excerpt.as_deref(),
+                Some(r#"<div class="unexpected">text</div>"#)
+            );
+        }
+    }
+
+    mod poll {
+        use insta::assert_json_snapshot;
+
+        use super::*;
+
+        fn parse_poll_from_fixture(
+            fixture_name: &str,
+            locale: Locale,
+        ) -> ParseResult<PollAttachment> {
+            let fixture_path = format!(
+                "{}/tests/fixtures/{}",
+                env!("CARGO_MANIFEST_DIR"),
+                fixture_name
+            );
+            let html = std::fs::read_to_string(&fixture_path).expect("fixture file should exist");
+
+            let html = scraper::Html::parse_fragment(&html);
+            parse_poll(
+                html.root_element()
+                    .first_element_child()
+                    .expect("top element is <html> and its first child is our input fragment"),
+                locale,
+            )
+        }
+
+        #[test]
+        fn test_text_poll() -> ParseResult<()> {
+            let poll = parse_poll_from_fixture("poll_text.html", Locale::Th)?;
+
+            assert_json_snapshot!(poll, @r#"
+            {
+              "total_votes": 15000,
+              "items": [
+                {
+                  "text": "ร้องเพลง",
+                  "percentage": "61%",
+                  "percentage_value": 61.0,
+                  "estimated_votes": 9150,
+                  "selected": false
+                },
+                {
+                  "text": "เล่นเกม",
+                  "percentage": "27%",
+                  "percentage_value": 27.0,
+                  "estimated_votes": 4050,
+                  "selected": true
+                },
+                {
+                  "text": "พูดคุย",
+                  "percentage": "12%",
+                  "percentage_value": 12.0,
+                  "estimated_votes": 1800,
+                  "selected": false
+                }
+              ]
+            }
+            "#);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_image_poll() -> ParseResult<()> {
+            let poll = parse_poll_from_fixture("poll_image.html", Locale::En)?;
+
+            assert_json_snapshot!(poll, @r#"
+            {
+              "total_votes": 2345,
+              "items": [
+                {
+                  "text": "Summer outfit",
+                  "percentage": "45%",
+                  "percentage_value": 45.0,
+                  "estimated_votes": 1055,
+                  "image": "https://yt3.ggpht.com/poll-option-a=s264-nd-v1",
+                  "selected": false
+                },
+                {
+                  "text": "Winter outfit",
+                  "percentage": "55%",
+                  "percentage_value": 55.0,
+                  "estimated_votes": 1290,
+                  "image": "https://yt3.ggpht.com/poll-option-b=s264-nd-v1",
+                  "selected": false
+                }
+              ]
+            }
+            "#);
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_hidden_result() {
+            let html = scraper::Html::parse_fragment(
+                r#"<span class="vote-percentage"></span><span class="vote-percentage">12.5 %</span><span class="vote-percentage">n/a</span>"#,
+            );
+            let percentages = select_all(html.root_element(), ".vote-percentage");
+            assert_eq!(parse_percentage(percentages[0]).unwrap(), None);
+            assert_eq!(parse_percentage(percentages[1]).unwrap(), Some(12.5));
+            assert!(parse_percentage(percentages[2]).is_err());
+        }
+    }
+
+    mod author {
+        use super::*;
+
+        fn parse_author_fragment(html: &str) -> ParseResult<AuthorChannel> {
+            let html = scraper::Html::parse_fragment(html);
+            let header = html
+                .root_element()
+                .first_element_child()
+                .expect("top element is <html> and its first child is our input fragment");
+            let author = select_one(header, "#author-text")?;
+            let name = author.text().map(str::trim).collect::<String>();
+            parse_author_channel(header, author, &name)
+        }
+
+        #[test]
+        fn test_post_author() -> ParseResult<()> {
+            let channel = parse_author_fragment(
+                r#"<div id="header"><a id="author-thumbnail" href="/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw"><yt-img-shadow><img id="img" src="https://yt3.ggpht.com/mel=s76"></yt-img-shadow></a>
+<div id="header-author"><a id="author-text" href="/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw"><span>Yozora Mel Ch. 夜空メルチャンネル</span></a></div></div>"#,
+            )?;
+            assert_eq!(
+                channel,
+                AuthorChannel {
+                    url: Some("/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw".to_owned()),
+                    channel_id: Some("UCD8HOxPs4Xvsm8H0ZxXGiBw".to_owned()),
+                    handle: None,
+                    avatar: Some("https://yt3.ggpht.com/mel=s76".to_owned()),
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_author_without_link() -> ParseResult<()> {
+            // name not linked, the avatar's link is used instead
+            let channel = parse_author_fragment(
+                r#"<div id="header"><div id="author-thumbnail"><a href="/@%E9%BE%8D-x6w7c"><img id="img" src=""></a></div>
+<span id="author-text">@龍-x6w7c</span></div>"#,
+            )?;
+            assert_eq!(
+                channel,
+                AuthorChannel {
+                    url: Some("/@%E9%BE%8D-x6w7c".to_owned()),
+                    channel_id: None,
+                    handle: Some("@龍-x6w7c".to_owned()),
+                    avatar: None,
+                }
+            );
+
+            let channel = parse_author_fragment(
+                r#"<div id="header"><span id="author-text">@someone</span></div>"#,
+            )?;
+            assert_eq!(channel.handle.as_deref(), Some("@someone"));
+            assert_eq!(channel.url, None);
+            Ok(())
+        }
+    }
+
+    mod comment_thread {
+        use insta::assert_json_snapshot;
+        use std::collections::HashMap;
+
+        use super::*;
+
+        fn parse_thread_from_fixture(fixture_name: &str) -> ParseResult<CommentThread> {
+            let resolver = EmoteResolver::with_mapping(HashMap::default());
+
+            // Load HTML fixture
+            let fixture_path = format!(
+                "{}/tests/fixtures/{}",
+                env!("CARGO_MANIFEST_DIR"),
+                fixture_name
+            );
+            let html = std::fs::read_to_string(&fixture_path).expect("fixture file should exist");
+
+            // Parse HTML and extract comment thread
+            let html = scraper::Html::parse_fragment(&html);
+            parse_comment_thread(
+                html.root_element()
+                    .first_element_child()
+                    .expect("top element is <html> and its first child is our input fragment"),
+                &ParseContext {
+                    locale: Some(Locale::Th),
+                    captured_at: Some("2024-01-17T12:00:00Z".parse().unwrap()),
+                    ..ParseContext::new(&resolver)
+                },
+            )
+        }
+
+        #[test]
+        fn test_threaded_replied() {
+            let thread = parse_thread_from_fixture("threaded_replied.html").unwrap();
+
+            assert_json_snapshot!(thread, @r#"
+            {
+              "author": "@龍-x6w7c",
+              "author_channel": {
+                "url": "/@%E9%BE%8D-x6w7c",
+                "channel_id": null,
+                "handle": "@龍-x6w7c",
+                "avatar": "https://yt3.ggpht.com/c2jtAcDE09qA6qWSRz5p6dto9_wr5KkPvsOItBoqTvdZrO6hGtetolC3VX6URlFoDha-2flh=s88-c-k-c0x00ffffff-no-rj"
+              },
+              "content": [
+                {
+                  "type": "text",
+                  "text": "今日は本当にいいライブだったよ!!6年間本当にありがとう"
+                },
+                {
+                  "type": "emoji",
+                  "emoji": "😊"
+                }
+              ],
+              "publish_time": {
+                "text": "20 ชั่วโมงที่ผ่านมา",
+                "earliest": "2024-01-16T15:00:00Z",
+                "latest": "2024-01-16T16:00:00Z"
+              },
+              "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg",
+              "comment_id": "UgylRbHxm0aafAMaD514AaABAg",
+              "parent_comment_id": null,
+              "post_id": "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+              "sponsor_duration": null,
+              "sponsor_badge": null,
+              "like": 300,
+              "replies": [
+                {
+                  "author": "@Wolfs97",
+                  "author_channel": {
+                    "url": "/@Wolfs97",
+                    "channel_id": null,
+                    "handle": "@Wolfs97",
+                    "avatar": "https://yt3.ggpht.com/gioyBPBCVVYq2mD4TolCakcI-KLtOpRYfAjhFKj8sriLZYmTNoMwOtDlbft9AeuAvmSruqKGrg=s88-c-k-c0x00ffffff-no-rj"
+                  },
+                  "content": [
+                    {
+                      "type": "text",
+                      "text": "悲しい"
+                    }
+                  ],
+                  "publish_time": {
+                    "text": "16 ชั่วโมงที่ผ่านมา",
+                    "earliest": "2024-01-16T19:00:00Z",
+                    "latest": "2024-01-16T20:00:00Z"
+                  },
+                  "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARF8_UOHFLV",
+                  "comment_id": "UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARF8_UOHFLV",
+                  "parent_comment_id": "UgylRbHxm0aafAMaD514AaABAg",
+                  "post_id": "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                  "sponsor_duration": null,
+                  "sponsor_badge": null,
+                  "like": 7
+                },
+                {
+                  "author": "@LolXD-rl7gg",
+                  "author_channel": {
+                    "url": "/@LolXD-rl7gg",
+                    "channel_id": null,
+                    "handle": "@LolXD-rl7gg",
+                    "avatar": "https://yt3.ggpht.com/ytc/AIdro_nbpIyw9RERWWeYVcxAB1eIBIpGS09nJ0NDBVgNNe0nbEppDM0SbBGcKOhumeWeRWLvPA=s88-c-k-c0x00ffffff-no-rj"
+                  },
+                  "content": [
+                    {
+                      "type": "text",
+                      "text": "Amaneeee"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😭"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😭"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😭"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😭"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    },
+                    {
+                      "type": "text",
+                      "text": " thank you for past 6 years so "
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😭"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😭"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "💙"
+                    }
+                  ],
+                  "publish_time": {
+                    "text": "18 ชั่วโมงที่ผ่านมา",
+                    "earliest": "2024-01-16T17:00:00Z",
+                    "latest": "2024-01-16T18:00:00Z"
+                  },
+                  "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8AREwUJ0hDAD",
+                  "comment_id": "UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8AREwUJ0hDAD",
+                  "parent_comment_id": "UgylRbHxm0aafAMaD514AaABAg",
+                  "post_id": "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                  "sponsor_duration": null,
+                  "sponsor_badge": null,
+                  "like": 5
+                },
+                {
+                  "author": "@괜찮아이츠키",
+                  "author_channel": {
+                    "url": "/@%EA%B4%9C%EC%B0%AE%EC%95%84%EC%9D%B4%EC%B8%A0%ED%82%A4",
+                    "channel_id": null,
+                    "handle": "@괜찮아이츠키",
+                    "avatar": "https://yt3.ggpht.com/LysQOB7VZ33jnEqq1hsF3bI0E_nSrx0mA_U62z7W0IOw08rbhFJuQDdRteKPj-YDW0yHBg54Hw=s88-c-k-c0x00ffffff-no-rj"
+                  },
+                  "content": [
+                    {
+                      "type": "text",
+                      "text": "今まで本当にありがとう"
+                    },
+                    {
+                      "type": "emoji",
+                      "emoji": "😢"
+                    },
+                    {
+                      "type": "text",
+                      "text": "ゆっくり休んでね。また何処かで会えたらな。"
+                    }
+                  ],
+                  "publish_time": {
+                    "text": "9 ชั่วโมงที่ผ่านมา",
+                    "earliest": "2024-01-17T02:00:00Z",
+                    "latest": "2024-01-17T03:00:00Z"
+                  },
+                  "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARFsyJznk92",
+                  "comment_id": "UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARFsyJznk92",
+                  "parent_comment_id": "UgylRbHxm0aafAMaD514AaABAg",
+                  "post_id": "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                  "sponsor_duration": {
+                    "text": "สมาชิกใหม่",
+                    "months": 0,
+                    "new_member": true
+                  },
+                  "sponsor_badge": "https://yt3.ggpht.com/l3iBRUbum0N6BsR7SJNKvR4ZlVox7gVDDGKClQQ4fBHBm-tuxM7nkhJacX49DkIfo55YYUyL9C0=s32-k-nd",
+                  "like": 5,
+                  "replies": [
+                    {
+                      "author": "@いをと",
+                      "author_channel": {
+                        "url": "/@%E3%81%84%E3%82%92%E3%81%A8",
+                        "channel_id": null,
+                        "handle": "@いをと",
+                        "avatar": "https://yt3.ggpht.com/JD7RZubT3DTyrxTCf34lyO3ltpiyDTQjyDrWNv62FWWMOZUNuxkKEI23lbx8EcSVkdlWUf523Q=s88-c-k-c0x00ffffff-no-rj"
+                      },
+                      "content": [
+                        {
+                          "type": "mention",
+                          "text": "@괜찮아이츠키",
+                          "url": "/channel/UCIRjccPaf9aWkNu5cxXJEUg",
+                          "target": {
+                            "kind": "channel",
+                            "id": "UCIRjccPaf9aWkNu5cxXJEUg"
+                          }
+                        },
+                        {
+                          "type": "text",
+                          "text": " どーせ転生すんだろ笑笑すぐ会えるよー笑笑無意味過ぎる"
+                        }
+                      ],
+                      "publish_time": {
+                        "text": "8 นาทีที่ผ่านมา",
+                        "earliest": "2024-01-17T11:51:00Z",
+                        "latest": "2024-01-17T11:52:00Z"
+                      },
+                      "url": "/post/UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR?lc=UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARGsW6iq7S5",
+                      "comment_id": "UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARGsW6iq7S5",
+                      "parent_comment_id": "UgylRbHxm0aafAMaD514AaABAg",
+                      "post_id": "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                      "sponsor_duration": null,
+                      "sponsor_badge": null,
+                      "like": 0
+                    }
+                  ]
+                }
+              ]
+            }
+            "#);
+        }
+
+        #[test]
+        fn test_creator_signals() -> ParseResult<()> {
+            let thread = parse_thread_from_fixture("creator_signals.html")?;
+            let signals = |c: &Comment| {
+                (
+                    c.hearted,
+                    c.pinned,
+                    c.channel_owner,
+                    c.author_badge,
+                    c.edited,
+                )
+            };
+
+            let comment = &thread.comment;
+            assert_eq!(signals(comment), (true, true, false, None, true));
+            assert_eq!(comment.publish_time.text, "1 ปีที่แล้ว (แก้ไขแล้ว)");
+            assert!(comment.publish_time.earliest.is_some());
+
+            let [owner, artist] = &thread.replies[..] else {
+                panic!("expected 2 replies, found {}", thread.replies.len());
+            };
+            assert_eq!(owner.comment.author, "@YozoraMel");
+            // name is hidden behind the badge, but still linked
+            assert_eq!(
+                owner.comment.author_channel.handle.as_deref(),
+                Some("@YozoraMel")
+            );
+            assert_eq!(
+                signals(&owner.comment),
+                (false, false, true, Some(AuthorBadge::Verified), false)
+            );
+            assert_eq!(
+                signals(&artist.comment),
+                (true, false, false, Some(AuthorBadge::Artist), false)
+            );
+
+            // other fixtures have the markers' empty containers only
+            let thread = parse_thread_from_fixture("pre_thread_replied.html")?;
+            assert_eq!(signals(&thread.comment), (false, false, false, None, false));
+            Ok(())
+        }
+
+        #[test]
+        fn test_comment_ids() -> ParseResult<()> {
+            for (fixture, post_id) in [
+                (
+                    "threaded_replied.html",
+                    "UgkxK7l4q43MGEyzwAhZnvutiv2x57ZmyRXR",
+                ),
+                (
+                    "pre_thread_replied.html",
+                    "Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                ),
+            ] {
+                let mut post = crate::model::tests::post(post_id);
+                post.post.comments = Some(vec![parse_thread_from_fixture(fixture)?]);
+                assert_eq!(post.post.comment_id_mismatches(), [], "{fixture}");
+            }
+
+            // replies of replies belong to the top-level comment
+            let thread = parse_thread_from_fixture("threaded_replied.html")?;
+            let nested = &thread.replies[2].replies[0].comment;
+            assert_eq!(
+                nested.comment_id.as_deref(),
+                Some("UgylRbHxm0aafAMaD514AaABAg.AREhkBaexO8ARGsW6iq7S5")
+            );
+            assert_eq!(nested.parent_comment_id, thread.comment.comment_id);
+            Ok(())
+        }
+
+        #[test]
+        fn test_pre_thread_replied() -> ParseResult<()> {
+            let thread = parse_thread_from_fixture("pre_thread_replied.html")?;
+
+            assert_json_snapshot!(thread, @r#"
+            {
+              "author": "@Redacted",
+              "author_channel": {
+                "url": "/@Redacted",
+                "channel_id": null,
+                "handle": "@Redacted",
+                "avatar": "https://yt3.ggpht.com/ytc/AIdro_ni944x4czXKfW2Y-R_EYe1dZN-Xyvq7sZA2uH1V40=s88-c-k-c0x00ffffff-no-rj"
+              },
+              "content": [
+                {
+                  "type": "text",
+                  "text": "Eh? Both links are in Japanese."
+                }
+              ],
+              "publish_time": {
+                "text": "1 ปีที่แล้ว",
+                "earliest": "2022-01-17T12:00:00Z",
+                "latest": "2023-01-17T12:00:00Z"
+              },
+              "url": "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+              "comment_id": "UgwDTW4THFsDyEAMYXN4AaABAg",
+              "parent_comment_id": null,
+              "post_id": "Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+              "sponsor_duration": null,
+              "sponsor_badge": null,
+              "like": 4,
+              "replies": [
+                {
+                  "author": "@Redacted",
+                  "author_channel": {
+                    "url": "/@Redacted",
+                    "channel_id": null,
+                    "handle": "@Redacted",
+                    "avatar": "https://yt3.ggpht.com/uk0_hl-Ea4vMDSAcK45-v2g7ZcAsFM8ZYvlzWyH90lVKUG4CQyFaFBnjYVMw8uChMQvCWR6H=s88-c-k-c0x00ffffff-no-rj"
+                  },
+                  "content": [
+                    {
+                      "type": "text",
+                      "text": "One says it's the Japan dubbed version and the other is the original English voices"
+                    }
+                  ],
+                  "publish_time": {
+                    "text": "1 ปีที่แล้ว",
+                    "earliest": "2022-01-17T12:00:00Z",
+                    "latest": "2023-01-17T12:00:00Z"
+                  },
+                  "url": "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMOR-vpM9U&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                  "comment_id": "UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMOR-vpM9U",
+                  "parent_comment_id": "UgwDTW4THFsDyEAMYXN4AaABAg",
+                  "post_id": "Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                  "sponsor_duration": null,
+                  "sponsor_badge": null,
+                  "like": 2
+                },
+                {
+                  "author": "@Redacted",
+                  "author_channel": {
+                    "url": "/@Redacted",
+                    "channel_id": null,
+                    "handle": "@Redacted",
+                    "avatar": "https://yt3.ggpht.com/vb33E_VxrfrhzDqPM8zB5UuXRMqesI0M243UoNpOwaMvMKTyBYSAvbTdJmHMSqLXAJ5q3p-VLXQ=s88-c-k-c0x00ffffff-no-rj"
+                  },
+                  "content": [
+                    {
+                      "type": "text",
+                      "text": "2nd link has English audio"
+                    }
+                  ],
+                  "publish_time": {
+                    "text": "1 ปีที่แล้ว",
+                    "earliest": "2022-01-17T12:00:00Z",
+                    "latest": "2023-01-17T12:00:00Z"
+                  },
+                  "url": "/channel/UCD8HOxPs4Xvsm8H0ZxXGiBw/community?lc=UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMjoxWYh6a&lb=Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                  "comment_id": "UgwDTW4THFsDyEAMYXN4AaABAg.9iMHEysqBs59iMjoxWYh6a",
+                  "parent_comment_id": "UgwDTW4THFsDyEAMYXN4AaABAg",
+                  "post_id": "Ugkx2lhAQ1LhH8eGLjoiCuxtsbCKhFChlt8A",
+                  "sponsor_duration": null,
+                  "sponsor_badge": null,
+                  "like": 3
+                }
+              ]
+            }
+            "#);
+
+            Ok(())
+        }
+    }
+}
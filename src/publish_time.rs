@@ -0,0 +1,181 @@
+use jiff::{Span, Timestamp, Zoned, tz::TimeZone};
+
+use crate::locale::Locale;
+
+/// Unit of a relative time, ie. `20 hours ago`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeUnit {
+    fn span(self, n: i64) -> Option<Span> {
+        let span = Span::new();
+        match self {
+            Self::Second => span.try_seconds(n),
+            Self::Minute => span.try_minutes(n),
+            Self::Hour => span.try_hours(n),
+            Self::Day => span.try_days(n),
+            Self::Week => span.try_weeks(n),
+            Self::Month => span.try_months(n),
+            Self::Year => span.try_years(n),
+        }
+        .ok()
+    }
+}
+
+/// Publish time as displayed on the page, with the absolute time range it refers to.
+///
+/// YouTube only shows relative time (ie. `20 ชั่วโมงที่ผ่านมา`) rounded down to its largest unit,
+/// so `N units ago` means somewhere between `N` and `N + 1` units before the page was captured.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct PublishTime {
+    pub text: String,
+    pub earliest: Option<Timestamp>,
+    pub latest: Option<Timestamp>,
+}
+
+impl PublishTime {
+    /// Resolve relative publish time `text` against the time the page was captured.
+    /// Range is left empty if either the text cannot be parsed or capture time is unknown.
+    pub fn resolve(text: String, locale: Locale, captured_at: Option<Timestamp>) -> Self {
+        let range = captured_at.zip(locale.parse_relative_time(&text)).and_then(
+            |(captured_at, (n, unit))| {
+                let captured_at = captured_at.to_zoned(TimeZone::UTC);
+                let ago = |n| -> Option<Timestamp> {
+                    captured_at
+                        .checked_sub(unit.span(n)?)
+                        .ok()
+                        .as_ref()
+                        .map(Zoned::timestamp)
+                };
+                Some((ago(n + 1)?, ago(n)?))
+            },
+        );
+
+        Self {
+            text,
+            earliest: range.map(|(earliest, _)| earliest),
+            latest: range.map(|(_, latest)| latest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let captured_at: Timestamp = "2024-01-17T12:00:00Z".parse().unwrap();
+        let cases = [
+            (
+                Locale::Th,
+                "20 ชั่วโมงที่ผ่านมา",
+                "2024-01-16T15:00:00Z",
+                "2024-01-16T16:00:00Z",
+            ),
+            (
+                Locale::Th,
+                "8 นาทีที่ผ่านมา",
+                "2024-01-17T11:51:00Z",
+                "2024-01-17T11:52:00Z",
+            ),
+            (
+                Locale::Th,
+                "1 ปีที่แล้ว",
+                "2022-01-17T12:00:00Z",
+                "2023-01-17T12:00:00Z",
+            ),
+            (
+                Locale::Th,
+                "2 เดือนที่ผ่านมา (แก้ไขแล้ว)",
+                "2023-10-17T12:00:00Z",
+                "2023-11-17T12:00:00Z",
+            ),
+            (
+                Locale::En,
+                "20 hours ago",
+                "2024-01-16T15:00:00Z",
+                "2024-01-16T16:00:00Z",
+            ),
+            (
+                Locale::En,
+                "1 year ago",
+                "2022-01-17T12:00:00Z",
+                "2023-01-17T12:00:00Z",
+            ),
+            (
+                Locale::En,
+                "3 weeks ago (edited)",
+                "2023-12-20T12:00:00Z",
+                "2023-12-27T12:00:00Z",
+            ),
+            (
+                Locale::Ja,
+                "20 時間前",
+                "2024-01-16T15:00:00Z",
+                "2024-01-16T16:00:00Z",
+            ),
+            (
+                Locale::Ja,
+                "2 か月前",
+                "2023-10-17T12:00:00Z",
+                "2023-11-17T12:00:00Z",
+            ),
+            (
+                Locale::Ja,
+                "5 日前",
+                "2024-01-11T12:00:00Z",
+                "2024-01-12T12:00:00Z",
+            ),
+            (
+                Locale::Ko,
+                "20시간 전",
+                "2024-01-16T15:00:00Z",
+                "2024-01-16T16:00:00Z",
+            ),
+            (
+                Locale::Ko,
+                "2개월 전",
+                "2023-10-17T12:00:00Z",
+                "2023-11-17T12:00:00Z",
+            ),
+            (
+                Locale::Ko,
+                "30초 전",
+                "2024-01-17T11:59:29Z",
+                "2024-01-17T11:59:30Z",
+            ),
+        ];
+        for (locale, text, earliest, latest) in cases {
+            let time = PublishTime::resolve(text.to_owned(), locale, Some(captured_at));
+            assert_eq!(
+                time,
+                PublishTime {
+                    text: text.to_owned(),
+                    earliest: Some(earliest.parse().unwrap()),
+                    latest: Some(latest.parse().unwrap()),
+                },
+                "{locale:?} {text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_unknown() {
+        let captured_at: Timestamp = "2024-01-17T12:00:00Z".parse().unwrap();
+
+        let time = PublishTime::resolve("20 hours ago".to_owned(), Locale::En, None);
+        assert_eq!((time.earliest, time.latest), (None, None));
+
+        let time = PublishTime::resolve("20 hours ago".to_owned(), Locale::Th, Some(captured_at));
+        assert_eq!((time.earliest, time.latest), (None, None));
+    }
+}
@@ -0,0 +1,489 @@
+use regex::Regex;
+use std::{fmt::Write, ops::Not, sync::LazyLock};
+use url::Url;
+
+use crate::link::{LinkTarget, YOUTUBE_ORIGIN};
+
+/// Formatted content of a post or comment, as a sequence of spans.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(transparent)]
+pub struct RichText(pub Vec<Span>);
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Span {
+    Text {
+        text: String,
+        #[serde(flatten)]
+        style: Style,
+    },
+    Link {
+        text: String,
+        url: String,
+        target: LinkTarget,
+    },
+    /// Link to a channel, ie. `@handle`.
+    Mention {
+        text: String,
+        url: String,
+        target: LinkTarget,
+    },
+    Hashtag {
+        text: String,
+        url: String,
+        target: LinkTarget,
+    },
+    /// Link to a time in a video, ie. `1:23`.
+    Timestamp {
+        text: String,
+        url: String,
+        target: LinkTarget,
+    },
+    /// Unicode emoji.
+    Emoji { emoji: String },
+    /// Custom channel emote, `name` is `None` if it could not be resolved.
+    Emote { name: Option<String>, url: String },
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+pub struct Style {
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub strikethrough: bool,
+}
+
+/// Output format of [`RichText::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Plain,
+    Markdown,
+    Html,
+}
+
+impl RichText {
+    /// Append text, merging it into the previous span if they have the same style.
+    pub fn push_text(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(Span::Text {
+            text: last,
+            style: last_style,
+        }) = self.0.last_mut()
+            && *last_style == style
+        {
+            last.push_str(text);
+            return;
+        }
+        self.0.push(Span::Text {
+            text: text.to_owned(),
+            style,
+        });
+    }
+
+    /// Append link, classified by its text and url.
+    /// Surrounding whitespace of the link text (ie. `&nbsp;@handle&nbsp;`) is kept outside the link.
+    pub fn push_link(&mut self, text: &str, url: &str, style: Style) {
+        let trimmed = text.trim();
+        let start = text.len() - text.trim_start().len();
+        let trailing = &text[start + trimmed.len()..];
+        self.push_text(&text[..start], style);
+
+        let target = LinkTarget::classify(url);
+        let (text, url) = (trimmed.to_owned(), url.to_owned());
+        self.0.push(match target {
+            _ if text.starts_with('@') => Span::Mention { text, url, target },
+            LinkTarget::Hashtag { .. } => Span::Hashtag { text, url, target },
+            LinkTarget::Timestamp { .. } if is_timestamp(&text) => {
+                Span::Timestamp { text, url, target }
+            }
+            _ => Span::Link { text, url, target },
+        });
+
+        self.push_text(trailing, style);
+    }
+
+    /// Remove leading and trailing whitespace.
+    pub fn trim(&mut self) {
+        if let Some(Span::Text { text, .. }) = self.0.first_mut() {
+            *text = text.trim_start().to_owned();
+        }
+        if let Some(Span::Text { text, .. }) = self.0.last_mut() {
+            *text = text.trim_end().to_owned();
+        }
+        self.0
+            .retain(|span| !matches!(span, Span::Text { text, .. } if text.is_empty()));
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Plain => self.to_plain(),
+            Format::Markdown => self.to_markdown(),
+            Format::Html => self.to_html(),
+        }
+    }
+
+    /// Text as displayed, custom emotes are written as `:_name:`.
+    pub fn to_plain(&self) -> String {
+        let mut out = String::new();
+        for span in &self.0 {
+            match span {
+                Span::Text { text, .. }
+                | Span::Link { text, .. }
+                | Span::Mention { text, .. }
+                | Span::Hashtag { text, .. }
+                | Span::Timestamp { text, .. } => out.push_str(text),
+                Span::Emoji { emoji } => out.push_str(emoji),
+                Span::Emote {
+                    name: Some(name), ..
+                } => write!(out, ":_{name}:").unwrap(),
+                Span::Emote { name: None, .. } => {}
+            }
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for span in &self.0 {
+            match span {
+                Span::Text { text, style } => {
+                    // markers must be right next to the text to take effect
+                    let trimmed = text.trim();
+                    if trimmed.is_empty() {
+                        out.push_str(&escape_markdown(text));
+                        continue;
+                    }
+                    let start = text.len() - text.trim_start().len();
+                    let marker = match (style.bold, style.italic) {
+                        (true, true) => "***",
+                        (true, false) => "**",
+                        (false, true) => "*",
+                        (false, false) => "",
+                    };
+                    let strike = if style.strikethrough { "~~" } else { "" };
+                    write!(
+                        out,
+                        "{}{strike}{marker}{}{marker}{strike}{}",
+                        escape_markdown(&text[..start]),
+                        escape_markdown(trimmed),
+                        escape_markdown(&text[start + trimmed.len()..]),
+                    )
+                    .unwrap();
+                }
+                Span::Link { text, url, target }
+                | Span::Mention { text, url, target }
+                | Span::Hashtag { text, url, target }
+                | Span::Timestamp { text, url, target } => match href(url, target) {
+                    Some(href) => {
+                        write!(out, "[{}]({})", escape_markdown(text), encode_url(&href)).unwrap()
+                    }
+                    None => out.push_str(&escape_markdown(text)),
+                },
+                Span::Emoji { emoji } => out.push_str(emoji),
+                Span::Emote {
+                    name: Some(name), ..
+                } => write!(out, ":\\_{}:", escape_markdown(name)).unwrap(),
+                Span::Emote { name: None, url } => {
+                    write!(out, "![emote]({})", encode_url(&absolute_url(url))).unwrap()
+                }
+            }
+        }
+        // hard line breaks
+        out.replace('\n', "  \n")
+    }
+
+    pub fn to_html(&self) -> String {
+        self.to_html_with(|_| None)
+    }
+
+    /// HTML with custom emotes pointing to `emote_src(name)` when it returns a source, ie. a local copy.
+    /// `:_name:` codes left in the text are also replaced by the emote image if it has a source.
+    pub fn to_html_with(&self, emote_src: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        for span in &self.0 {
+            match span {
+                Span::Text { text, style } => {
+                    let tags = [
+                        (style.bold, "b"),
+                        (style.italic, "i"),
+                        (style.strikethrough, "s"),
+                    ];
+                    for (_, tag) in tags.iter().filter(|(on, _)| *on) {
+                        write!(out, "<{tag}>").unwrap();
+                    }
+                    emote_codes_html(&mut out, text, &emote_src);
+                    for (_, tag) in tags.iter().rev().filter(|(on, _)| *on) {
+                        write!(out, "</{tag}>").unwrap();
+                    }
+                }
+                Span::Link { text, url, target } => link_html(&mut out, "link", text, url, target),
+                Span::Mention { text, url, target } => {
+                    link_html(&mut out, "mention", text, url, target)
+                }
+                Span::Hashtag { text, url, target } => {
+                    link_html(&mut out, "hashtag", text, url, target)
+                }
+                Span::Timestamp { text, url, target } => {
+                    link_html(&mut out, "timestamp", text, url, target)
+                }
+                Span::Emoji { emoji } => out.push_str(&escape_html(emoji)),
+                Span::Emote { name, url } => {
+                    let src = name
+                        .as_deref()
+                        .and_then(&emote_src)
+                        .unwrap_or_else(|| absolute_url(url));
+                    let alt = name.as_deref().map(|name| format!(":_{name}:"));
+                    emote_html(&mut out, &src, alt.as_deref().unwrap_or_default());
+                }
+            }
+        }
+        out.replace('\n', "<br>\n")
+    }
+}
+
+fn emote_html(out: &mut String, src: &str, alt: &str) {
+    write!(
+        out,
+        r#"<img class="emote" src="{}" alt="{}">"#,
+        escape_html(src),
+        escape_html(alt)
+    )
+    .unwrap();
+}
+
+/// Escaped `text` with the `:_name:` codes having a source replaced by emote images.
+fn emote_codes_html(out: &mut String, text: &str, emote_src: impl Fn(&str) -> Option<String>) {
+    static EMOTE_CODE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r":_([A-Za-z0-9_-]+):").unwrap());
+
+    let mut last = 0;
+    for captures in EMOTE_CODE.captures_iter(text) {
+        let code = captures.get(0).unwrap();
+        if let Some(src) = emote_src(&captures[1]) {
+            out.push_str(&escape_html(&text[last..code.start()]));
+            emote_html(out, &src, code.as_str());
+            last = code.end();
+        }
+    }
+    out.push_str(&escape_html(&text[last..]));
+}
+
+fn link_html(out: &mut String, class: &str, text: &str, url: &str, target: &LinkTarget) {
+    match href(url, target) {
+        Some(href) => write!(
+            out,
+            r#"<a class="{class}" href="{}">{}</a>"#,
+            escape_html(&href),
+            escape_html(text)
+        )
+        .unwrap(),
+        None => out.push_str(&escape_html(text)),
+    }
+}
+
+/// Whether text looks like a video timestamp, ie. `1:23` or `1:02:03`.
+fn is_timestamp(text: &str) -> bool {
+    let parts = text.split(':').collect::<Vec<_>>();
+    (2..=3).contains(&parts.len())
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Link destination, skipping YouTube's redirect page for external links.
+/// `None` unless it is an http(s) url, so `javascript:` and other schemes are never linked to.
+pub(crate) fn href(url: &str, target: &LinkTarget) -> Option<String> {
+    let href = match target {
+        LinkTarget::External { url } => url.clone(),
+        _ => absolute_url(url),
+    };
+    Url::parse(&href)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+        .then_some(href)
+}
+
+pub(crate) fn absolute_url(url: &str) -> String {
+    if url.starts_with('/') {
+        format!("{YOUTUBE_ORIGIN}{url}")
+    } else {
+        url.to_owned()
+    }
+}
+
+/// Percent-encode characters that are not allowed in urls (ie. spaces, `<`, `>`, parentheses and non-ASCII),
+/// so `url` can be used as is as a Markdown link destination.
+pub(crate) fn encode_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for b in url.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~:/?#[]@!$&'*+,;=%".contains(&b) {
+            out.push(b as char);
+        } else {
+            write!(out, "%{b:02X}").unwrap();
+        }
+    }
+    out
+}
+
+pub(crate) fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '~' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_str_eq;
+
+    fn sample() -> RichText {
+        let bold = Style {
+            bold: true,
+            ..Style::default()
+        };
+        let mut text = RichText::default();
+        text.push_text("  Hi ", Style::default());
+        text.push_link("\u{a0}@Amane\u{a0}", "/@Amane", Style::default());
+        text.push_text("read ", Style::default());
+        text.push_text("this *now* ", bold);
+        text.push_link("1:02:03", "/watch?v=abc&t=3723s", Style::default());
+        text.push_text("\n<3", Style::default());
+        text.0.push(Span::Emoji {
+            emoji: "💙".to_owned(),
+        });
+        text.0.push(Span::Emote {
+            name: Some("Rawr".to_owned()),
+            url: "https://yt3.ggpht.com/abc=s24".to_owned(),
+        });
+        text.push_text(" ", Style::default());
+        text.trim();
+        text
+    }
+
+    #[test]
+    fn test_push() {
+        let text = sample();
+        assert_eq!(
+            text.0[..3],
+            [
+                Span::Text {
+                    text: "Hi \u{a0}".to_owned(),
+                    style: Style::default()
+                },
+                Span::Mention {
+                    text: "@Amane".to_owned(),
+                    url: "/@Amane".to_owned(),
+                    target: LinkTarget::Handle {
+                        handle: "@Amane".to_owned()
+                    }
+                },
+                Span::Text {
+                    text: "\u{a0}read ".to_owned(),
+                    style: Style::default()
+                },
+            ]
+        );
+        assert!(matches!(text.0[4], Span::Timestamp { .. }));
+        assert!(matches!(text.0.last(), Some(Span::Emote { .. })));
+
+        let mut text = RichText::default();
+        text.push_link("#tag", "/hashtag/tag", Style::default());
+        text.push_link("1:23", "https://example.com", Style::default());
+        text.push_link(
+            "example.com",
+            "https://www.youtube.com/redirect?q=https%3A%2F%2Fexample.com",
+            Style::default(),
+        );
+        assert!(matches!(text.0[0], Span::Hashtag { .. }));
+        assert!(matches!(text.0[1], Span::Link { .. }));
+        assert_eq!(
+            text.to_html(),
+            r#"<a class="hashtag" href="https://www.youtube.com/hashtag/tag">#tag</a><a class="link" href="https://example.com">1:23</a><a class="link" href="https://example.com">example.com</a>"#
+        );
+
+        // only http(s) urls are linked
+        let mut text = RichText::default();
+        text.push_link("click", "javascript:alert(1)", Style::default());
+        text.push_link(
+            "me",
+            "https://www.youtube.com/redirect?q=javascript%3Aalert(1)",
+            Style::default(),
+        );
+        text.push_link("data", " data:text/html,<b>", Style::default());
+        assert_eq!(text.to_html(), "clickmedata");
+        assert_eq!(text.to_markdown(), "clickmedata");
+
+        let mut text = RichText::default();
+        text.push_link("x", "https://example.com/a b>(c)ก", Style::default());
+        assert_eq!(
+            text.to_markdown(),
+            "[x](https://example.com/a%20b%3E%28c%29%E0%B8%81)"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let text = sample();
+        assert_str_eq!(
+            text.to_plain(),
+            "Hi \u{a0}@Amane\u{a0}read this *now* 1:02:03\n<3💙:_Rawr:"
+        );
+        assert_str_eq!(
+            text.to_markdown(),
+            "Hi \u{a0}[@Amane](https://www.youtube.com/@Amane)\u{a0}read **this \\*now\\*** [1:02:03](https://www.youtube.com/watch?v=abc&t=3723s)  \n\\<3💙:\\_Rawr:"
+        );
+        assert_str_eq!(
+            text.to_html(),
+            "Hi \u{a0}<a class=\"mention\" href=\"https://www.youtube.com/@Amane\">@Amane</a>\u{a0}read <b>this *now* </b><a class=\"timestamp\" href=\"https://www.youtube.com/watch?v=abc&amp;t=3723s\">1:02:03</a><br>\n&lt;3💙<img class=\"emote\" src=\"https://yt3.ggpht.com/abc=s24\" alt=\":_Rawr:\">"
+        );
+    }
+
+    #[test]
+    fn test_html_with_local_emotes() {
+        let mut text = sample();
+        text.push_text(" :_Rawr::_Unknown: & :_Rawr", Style::default());
+        let local = |name: &str| (name == "Rawr").then(|| format!("emotes/{name}.png"));
+        assert_str_eq!(
+            text.to_html_with(local).rsplit("💙").next().unwrap(),
+            "<img class=\"emote\" src=\"emotes/Rawr.png\" alt=\":_Rawr:\"> <img class=\"emote\" src=\"emotes/Rawr.png\" alt=\":_Rawr:\">:_Unknown: &amp; :_Rawr"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use color_eyre::{
+    Result,
+    eyre::{Context, bail},
+};
+use regex::Regex;
+use scraper::{Html, Node, Selector};
+use serde::{Deserialize, Deserializer, de::Error};
+use std::{fs, path::Path};
+
+use crate::locale::Locale;
+
+/// Link only present in the page when comments are turned off.
+const COMMENTS_DISABLED_MARKER: &str = "support.google.com/youtube/answer/9706180";
+
+/// Set of named rules a downloaded post html must pass.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ruleset {
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Rule {
+    /// Name to report the rule by when it fails.
+    pub name: String,
+    #[serde(default)]
+    pub when: Condition,
+    #[serde(flatten)]
+    pub check: Check,
+}
+
+/// When a rule applies.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    #[default]
+    Always,
+    CommentsEnabled,
+    CommentsDisabled,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Check {
+    /// Raw html must contain `text`.
+    RequiredText { text: String },
+    /// Raw html must not contain `text`.
+    ForbiddenText { text: String },
+    /// No visible text node may contain `text`, ie. unexpanded "read more".
+    ForbiddenVisibleText { text: String },
+    /// No visible text node, trimmed, may match `pattern`, ie. an unexpanded "3 replies" button.
+    ForbiddenVisiblePattern {
+        #[serde(deserialize_with = "deserialize_regex")]
+        pattern: Regex,
+    },
+    /// Number of elements matching `selector` must be within `min..=max`.
+    Selector {
+        #[serde(deserialize_with = "deserialize_selector")]
+        selector: Selector,
+        #[serde(default)]
+        min: usize,
+        max: Option<usize>,
+    },
+    /// Text of every element matching `selector` must match `pattern`.
+    SelectorText {
+        #[serde(deserialize_with = "deserialize_selector")]
+        selector: Selector,
+        #[serde(deserialize_with = "deserialize_regex")]
+        pattern: Regex,
+    },
+}
+
+fn deserialize_selector<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Selector, D::Error> {
+    let selector = String::deserialize(deserializer)?;
+    Selector::parse(&selector).map_err(|err| D::Error::custom(format!("{selector:?}: {err}")))
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+impl Ruleset {
+    /// Built-in ruleset for the UI language.
+    pub fn builtin(locale: Locale) -> Self {
+        let ruleset = match locale {
+            Locale::Th => include_str!("../rules/th.json"),
+            Locale::En => include_str!("../rules/en.json"),
+            Locale::Ja => include_str!("../rules/ja.json"),
+            Locale::Ko => include_str!("../rules/ko.json"),
+        };
+        serde_json::from_str(ruleset).expect("built-in ruleset should be valid")
+    }
+
+    /// Load ruleset from a `.json` or `.toml` file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("fail to read ruleset {}", path.display()))?;
+        let ruleset = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&content)?,
+            Some("toml") => toml::from_str(&content)?,
+            _ => bail!(
+                "ruleset should be a .json or .toml file: {}",
+                path.display()
+            ),
+        };
+        Ok(ruleset)
+    }
+
+    /// Check post html, returning names of the failed rules.
+    pub fn check<'a>(&'a self, content: &str, html: &Html) -> Vec<&'a str> {
+        let comments_enabled = !content.contains(COMMENTS_DISABLED_MARKER);
+        let mut visible_texts = None;
+
+        self.rules
+            .iter()
+            .filter(|rule| match rule.when {
+                Condition::Always => true,
+                Condition::CommentsEnabled => comments_enabled,
+                Condition::CommentsDisabled => !comments_enabled,
+            })
+            .filter(|rule| {
+                let pass = match &rule.check {
+                    Check::RequiredText { text } => content.contains(text.as_str()),
+                    Check::ForbiddenText { text } => !content.contains(text.as_str()),
+                    Check::ForbiddenVisibleText { text } => !visible_texts
+                        .get_or_insert_with(|| collect_visible_texts(html))
+                        .iter()
+                        .any(|t| t.contains(text.as_str())),
+                    Check::ForbiddenVisiblePattern { pattern } => !visible_texts
+                        .get_or_insert_with(|| collect_visible_texts(html))
+                        .iter()
+                        .any(|t| pattern.is_match(t.trim())),
+                    Check::Selector { selector, min, max } => {
+                        let n = html.select(selector).count();
+                        *min <= n && max.is_none_or(|max| n <= max)
+                    }
+                    Check::SelectorText { selector, pattern } => html
+                        .select(selector)
+                        .all(|e| pattern.is_match(&e.text().collect::<String>())),
+                };
+                !pass
+            })
+            .map(|rule| rule.name.as_str())
+            .collect()
+    }
+}
+
+/// Text nodes not inside hidden elements.
+fn collect_visible_texts(html: &Html) -> Vec<&str> {
+    let mut texts = Vec::new();
+    let mut ft = vec![html.tree.root()];
+
+    while let Some(n) = ft.pop() {
+        if let Node::Text(text) = n.value() {
+            texts.push(&**text);
+        }
+
+        if let Some(element) = n.value().as_element()
+            && (element.attr("hidden").is_some() || element.attr("style") == Some("display: none;"))
+        {
+            continue;
+        }
+
+        ft.extend(n.children());
+    }
+
+    texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<'a>(ruleset: &'a Ruleset, content: &str) -> Vec<&'a str> {
+        ruleset.check(content, &Html::parse_document(content))
+    }
+
+    #[test]
+    fn test_builtin() {
+        for locale in Locale::ALL {
+            assert!(!Ruleset::builtin(locale).rules.is_empty(), "{locale:?}");
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let ruleset: Ruleset = serde_json::from_str(
+            r##"{
+                "rules": [
+                    { "name": "like", "kind": "required_text", "text": "Like" },
+                    { "name": "sort", "when": "comments_enabled", "kind": "required_text", "text": "Sort by" },
+                    { "name": "sort-disabled", "when": "comments_disabled", "kind": "forbidden_text", "text": "Sort by" },
+                    { "name": "read-more", "kind": "forbidden_visible_text", "text": "Read more" },
+                    { "name": "replies", "kind": "forbidden_visible_pattern", "pattern": "^\\d+ replies$" },
+                    { "name": "poll-count", "kind": "selector", "selector": "#poll", "max": 1 },
+                    { "name": "poll-result", "kind": "selector_text", "selector": "#poll", "pattern": "\\d+%" }
+                ]
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            check(
+                &ruleset,
+                r#"<div>Like</div><div>Sort by</div><div hidden>Read more</div>"#
+            ),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            check(&ruleset, r#"<div>Dislike</div><div>Read more</div>"#),
+            vec!["like", "sort", "read-more"]
+        );
+        // only the expander button itself, not comments talking about replies
+        assert_eq!(
+            check(
+                &ruleset,
+                r#"Like Sort by <p>no replies yet</p><p hidden>3 replies</p>"#
+            ),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            check(&ruleset, "Like Sort by <button>\n  12 replies\n</button>"),
+            vec!["replies"]
+        );
+        assert_eq!(
+            check(
+                &ruleset,
+                r#"<a href="https://support.google.com/youtube/answer/9706180">Like</a>Sort by"#
+            ),
+            vec!["sort-disabled"]
+        );
+        assert_eq!(
+            check(&ruleset, r#"Like Sort by <div id="poll">Yes 60%</div>"#),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            check(&ruleset, r#"Like Sort by <div id="poll">Yes</div>"#),
+            vec!["poll-result"]
+        );
+        assert_eq!(
+            check(
+                &ruleset,
+                r#"Like Sort by <div id="poll">1%</div><div id="poll">2%</div>"#
+            ),
+            vec!["poll-count"]
+        );
+    }
+
+    #[test]
+    fn test_load_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.toml");
+        fs::write(
+            &path,
+            r##"
+            [[rules]]
+            name = "like"
+            kind = "required_text"
+            text = "Like"
+
+            [[rules]]
+            name = "poll-count"
+            kind = "selector"
+            selector = "#poll-attachment:not([hidden])"
+            max = 1
+            "##,
+        )
+        .unwrap();
+
+        let ruleset = Ruleset::load(&path).unwrap();
+        assert_eq!(ruleset.rules.len(), 2);
+        assert_eq!(check(&ruleset, "Like"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        let err = serde_json::from_str::<Ruleset>(
+            r##"{ "rules": [{ "name": "x", "kind": "selector", "selector": "#[" }] }"##,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("#["), "{err}");
+    }
+}